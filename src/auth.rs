@@ -0,0 +1,106 @@
+use base64::Engine;
+use quote::quote;
+
+use crate::{dynamic, token_helpers::with_tokens};
+
+/// Writes the value of a header the way it is sent.
+/// This encodes the credentials of `Authorization: Basic user:password` to base64, like REST Client does.
+pub fn header_value(name: &str, value: &str) -> String {
+    match basic_credentials(name, value) {
+        Some((scheme, credentials)) => format!("{scheme} {}", basic(credentials)),
+        None => value.to_string(),
+    }
+}
+
+/// Writes the code for the value of a header, with its dynamic variables.
+/// Basic credentials with variables which are only known when the request is made are encoded to base64 then, so the `base64` crate needs to be a dependency.
+pub fn header_tokens(name: &str, value: &str) -> proc_macro2::TokenStream {
+    let Some((scheme, credentials)) =
+        basic_credentials(name, value).filter(|(_, credentials)| dynamic::is_runtime(credentials))
+    else {
+        return dynamic::value_tokens(&header_value(name, value));
+    };
+
+    let join_credentials = join_credentials_tokens();
+    let credentials = dynamic::value_tokens(credentials);
+
+    quote! {
+        {
+            #join_credentials
+
+            let credentials = #credentials;
+
+            match join_credentials(&credentials) {
+                Some(joined) => ::std::format!(
+                    "{} {}",
+                    #scheme,
+                    ::base64::Engine::encode(&::base64::engine::general_purpose::STANDARD, joined),
+                ),
+                None => ::std::format!("{} {}", #scheme, credentials),
+            }
+        }
+    }
+}
+
+/// Splits an `Authorization: Basic credentials` header into the scheme and the credentials.
+fn basic_credentials<'a>(name: &str, value: &'a str) -> Option<(&'a str, &'a str)> {
+    if !name.eq_ignore_ascii_case("Authorization") {
+        return None;
+    }
+
+    value
+        .split_once(' ')
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("Basic"))
+        .map(|(scheme, credentials)| (scheme, credentials.trim()))
+}
+
+/// Encodes `user:password` or `user password` credentials.
+fn basic(credentials: &str) -> String {
+    match join_credentials(credentials) {
+        Some(joined) => base64::engine::general_purpose::STANDARD.encode(joined),
+        None => credentials.to_string(),
+    }
+}
+
+with_tokens! {
+    join_credentials_tokens,
+    /// Joins `user:password` or `user password` credentials with a `:`.
+    /// Credentials which are already encoded give `None`, since base64 has neither a `:` nor a space.
+    fn join_credentials(credentials: &str) -> Option<String> {
+        match credentials.split_once(char::is_whitespace) {
+            _ if credentials.contains(':') => Some(credentials.to_string()),
+            Some((user, password)) => Some(format!("{user}:{}", password.trim())),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_credentials() {
+        assert_eq!(
+            header_value("Authorization", "Basic alice:secret"),
+            "Basic YWxpY2U6c2VjcmV0"
+        );
+        assert_eq!(
+            header_value("authorization", "basic alice secret"),
+            "basic YWxpY2U6c2VjcmV0"
+        );
+        assert_eq!(
+            header_value("Authorization", "Basic YWxpY2U6c2VjcmV0"),
+            "Basic YWxpY2U6c2VjcmV0"
+        );
+    }
+
+    #[test]
+    fn other() {
+        assert_eq!(header_value("Authorization", "Bearer abc"), "Bearer abc");
+        assert_eq!(
+            header_value("X-Note", "Basic alice:secret"),
+            "Basic alice:secret"
+        );
+    }
+}
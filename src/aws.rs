@@ -0,0 +1,495 @@
+use std::collections::BTreeMap;
+
+use proc_macro::Span;
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::{body::Body, directives::Directives, dynamic, headers, url::encode_component};
+
+const HELP: &str = "Try `Authorization: AWS <access key> <secret key> region:<region> service:<service>`, with an optional `token:<session token>`";
+
+/// Where the date goes in the canonical request, since it is only known when the request is signed.
+const DATE_MARKER: &str = "\0amz-date\0";
+
+/// Signs a request with AWS Signature Version 4.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Signer {
+    access_key: String,
+    secret_key: String,
+    region: String,
+    service: String,
+    canonical_request: String,
+    signed_headers: String,
+}
+
+/// Stops when a request builder asks for an AWS signature.
+/// The signature covers the body, which is only set after `request_builder!`, so the keys would be sent as they are.
+pub fn reject(directives: &Directives, headers: &[(String, String)]) {
+    if credentials_index(headers).is_some() || directives.has("aws-date") {
+        abort!(
+            Span::call_site(),
+            "`request_builder!` cannot sign requests with AWS Signature Version 4";
+            help = "The signature covers the body, so use `request!` to sign the request with its body"
+        );
+    }
+}
+
+/// Returns the index of the `Authorization: AWS ...` header.
+fn credentials_index(headers: &[(String, String)]) -> Option<usize> {
+    headers.iter().position(|(name, value)| {
+        name.eq_ignore_ascii_case("Authorization") && value.starts_with("AWS ")
+    })
+}
+
+/// Signs the request when it has an `Authorization: AWS <access key> <secret key> ...` header, like REST Client does.
+/// With a `# @aws-date 20150830T123600Z` directive the request is signed here, and the `Authorization` and `X-Amz-Date` headers are set.
+/// Otherwise the `Authorization` header is removed, and the returned signer writes the code which signs the request when it is made.
+pub fn sign(
+    method: &str,
+    uri: &str,
+    directives: &Directives,
+    headers: &mut Vec<(String, String)>,
+    body: &Body,
+) -> Option<Signer> {
+    let index = credentials_index(headers)?;
+    let (_, credentials) = headers.remove(index);
+
+    if dynamic::is_runtime(uri)
+        || headers.iter().any(|(_, value)| dynamic::is_runtime(value))
+        || body.runtime
+    {
+        abort!(
+            Span::call_site(),
+            "The AWS signature cannot sign values that are made when the request is made";
+            help = "Add a `# @random-seed 42` directive to make the random values when the macro runs"
+        );
+    }
+
+    if body.chunks.is_some() {
+        abort!(
+            Span::call_site(),
+            "Chunked bodies cannot be signed with AWS Signature Version 4";
+            help = "Remove the `Transfer-Encoding: chunked` header"
+        );
+    }
+
+    let mut words = credentials.split_whitespace().skip(1);
+    let (Some(access_key), Some(secret_key)) = (words.next(), words.next()) else {
+        abort!(
+            Span::call_site(),
+            "Missing the keys of the AWS signature";
+            help = "{}", HELP
+        );
+    };
+
+    let (mut region, mut service) = (None, None);
+    for word in words {
+        match word.split_once(':') {
+            Some(("token", token)) => {
+                headers.push(("X-Amz-Security-Token".to_string(), token.to_string()))
+            }
+            Some(("region", value)) => region = Some(value.to_string()),
+            Some(("service", value)) => service = Some(value.to_string()),
+            _ => abort!(
+                Span::call_site(),
+                "Unknown AWS signature option `{}`", word;
+                help = "{}", HELP
+            ),
+        }
+    }
+
+    let (authority, path, query) = split_uri(uri);
+
+    if headers::get(headers, "Host").is_none() {
+        match authority {
+            Some(authority) => headers.push(("Host".to_string(), authority.to_string())),
+            None => abort!(
+                Span::call_site(),
+                "The AWS signature needs the host of the request";
+                help = "Add a `Host` header or use an absolute URI"
+            ),
+        }
+    }
+
+    // Hosts like `execute-api.eu-west-1.amazonaws.com` have the service and region
+    let host = headers::get(headers, "Host")
+        .unwrap_or_default()
+        .to_string();
+    let mut labels = host
+        .strip_suffix(".amazonaws.com")
+        .into_iter()
+        .flat_map(|name| name.split('.'));
+    let (host_service, host_region) = (labels.next(), labels.next());
+
+    let Some(service) = service.or(host_service.map(str::to_string)) else {
+        abort!(
+            Span::call_site(),
+            "Missing the service of the AWS signature";
+            help = "{}", HELP
+        );
+    };
+    let region = region
+        .or(host_region.map(str::to_string))
+        .unwrap_or_else(|| "us-east-1".to_string());
+
+    let mut canonical_headers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, value) in headers.iter() {
+        let value: Vec<_> = value.split_whitespace().collect();
+        canonical_headers
+            .entry(name.to_lowercase())
+            .or_default()
+            .push(value.join(" "));
+    }
+    canonical_headers.insert(
+        "x-amz-date".to_string(),
+        Vec::from([DATE_MARKER.to_string()]),
+    );
+
+    let signed_headers: Vec<_> = canonical_headers.keys().map(String::as_str).collect();
+    let signed_headers = signed_headers.join(";");
+
+    // Only S3 signs the path as it is sent, other services encode it again
+    let path = match service.as_str() {
+        "s3" => path.to_string(),
+        _ => encode_path(path),
+    };
+
+    let canonical_request = [
+        method.to_string(),
+        path,
+        canonical_query(query),
+        canonical_headers
+            .iter()
+            .map(|(name, values)| format!("{name}:{}\n", values.join(",")))
+            .collect(),
+        signed_headers.clone(),
+        hex(&sha256(&body.data)),
+    ]
+    .join("\n");
+
+    let signer = Signer {
+        access_key: access_key.to_string(),
+        secret_key: secret_key.to_string(),
+        region,
+        service,
+        canonical_request,
+        signed_headers,
+    };
+
+    let Some(date) = directives.get("aws-date") else {
+        return Some(signer);
+    };
+
+    if !is_amz_date(date) {
+        abort!(
+            Span::call_site(),
+            "Invalid AWS date `{}`", date;
+            help = "The date is in UTC, like `# @aws-date 20150830T123600Z`"
+        );
+    }
+
+    headers.push(("X-Amz-Date".to_string(), date.to_string()));
+    headers.push(("Authorization".to_string(), signer.authorization(date)));
+
+    None
+}
+
+impl Signer {
+    /// Writes the code which signs the request when it is made, giving the `amz_date` and `authorization` values.
+    pub fn prelude_tokens(&self) -> proc_macro2::TokenStream {
+        let Self {
+            access_key,
+            secret_key,
+            region,
+            service,
+            canonical_request,
+            signed_headers,
+        } = self;
+        let (before, after) = canonical_request.split_once(DATE_MARKER).unwrap();
+        let secret_key = format!("AWS4{secret_key}");
+
+        quote! {
+            let (amz_date, authorization) = {
+                use ::hmac::Mac as _;
+                use ::sha2::Digest as _;
+
+                fn hmac(key: &[u8], data: &str) -> ::std::vec::Vec<u8> {
+                    let mut mac = ::hmac::Hmac::<::sha2::Sha256>::new_from_slice(key).unwrap();
+                    mac.update(data.as_bytes());
+                    mac.finalize().into_bytes().to_vec()
+                }
+
+                fn hex(data: &[u8]) -> ::std::string::String {
+                    data.iter().map(|byte| ::std::format!("{:02x}", byte)).collect()
+                }
+
+                let seconds = ::std::time::SystemTime::now()
+                    .duration_since(::std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+
+                // The days since 1970 to the civil date, from http://howardhinnant.github.io/date_algorithms.html
+                let days = seconds / 86400 + 719468;
+                let era = days / 146097;
+                let day_of_era = days - era * 146097;
+                let year_of_era =
+                    (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+                let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+                let month = (5 * day_of_year + 2) / 153;
+                let day = day_of_year - (153 * month + 2) / 5 + 1;
+                let month = if month < 10 { month + 3 } else { month - 9 };
+                let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+                let amz_date = ::std::format!(
+                    "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+                    year,
+                    month,
+                    day,
+                    seconds % 86400 / 3600,
+                    seconds % 3600 / 60,
+                    seconds % 60
+                );
+                let date = &amz_date[..8];
+                let scope = ::std::format!("{}/{}/{}/aws4_request", date, #region, #service);
+                let canonical_request = [#before, amz_date.as_str(), #after].concat();
+                let string_to_sign = ::std::format!(
+                    "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+                    amz_date,
+                    scope,
+                    hex(&::sha2::Sha256::digest(canonical_request.as_bytes()))
+                );
+                let key = hmac(
+                    &hmac(&hmac(&hmac(#secret_key.as_bytes(), date), #region), #service),
+                    "aws4_request",
+                );
+                let authorization = ::std::format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    #access_key,
+                    scope,
+                    #signed_headers,
+                    hex(&hmac(&key, &string_to_sign))
+                );
+
+                (amz_date, authorization)
+            };
+        }
+    }
+
+    /// Writes the headers with the values from [Signer::prelude_tokens].
+    pub fn header_tokens(&self) -> proc_macro2::TokenStream {
+        quote! {
+            .header("X-Amz-Date", amz_date)
+            .header("Authorization", authorization)
+        }
+    }
+
+    /// Returns the value of the `Authorization` header when the request is signed at the date.
+    fn authorization(&self, amz_date: &str) -> String {
+        let date = &amz_date[..8];
+        let scope = format!("{date}/{}/{}/aws4_request", self.region, self.service);
+        let canonical_request = self.canonical_request.replace(DATE_MARKER, amz_date);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex(&sha256(canonical_request.as_bytes()))
+        );
+
+        let key = [date, &self.region, &self.service, "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.secret_key).into_bytes(),
+                |key, data| hmac(&key, data),
+            );
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={}, Signature={}",
+            self.access_key,
+            self.signed_headers,
+            hex(&hmac(&key, &string_to_sign))
+        )
+    }
+}
+
+/// Splits the URI into its authority, path and query.
+fn split_uri(uri: &str) -> (Option<&str>, &str, &str) {
+    let (authority, rest) = match uri.split_once("://") {
+        Some((_, rest)) => {
+            let end = rest.find(['/', '?']).unwrap_or(rest.len());
+            (Some(&rest[..end]), &rest[end..])
+        }
+        None => (None, uri),
+    };
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let path = if path.is_empty() { "/" } else { path };
+
+    (authority, path, query)
+}
+
+/// Encodes each segment of the path.
+fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(encode_component)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Sorts the query parameters and encodes them the same way.
+fn canonical_query(query: &str) -> String {
+    let mut parameters: Vec<(String, String)> = query
+        .split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            (
+                encode_component(&decode(name)),
+                encode_component(&decode(value)),
+            )
+        })
+        .collect();
+    parameters.sort();
+
+    parameters
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Decodes the `%XX` escapes in a query parameter.
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escape = (bytes[index] == b'%')
+            .then(|| value.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Returns whether the date is like `20150830T123600Z`.
+fn is_amz_date(date: &str) -> bool {
+    date.len() == 16
+        && date.char_indices().all(|(index, c)| match index {
+            8 => c == 'T',
+            15 => c == 'Z',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(feature = "aws")]
+fn sha256(data: &[u8]) -> Vec<u8> {
+    use sha2::Digest;
+
+    sha2::Sha256::digest(data).to_vec()
+}
+
+#[cfg(feature = "aws")]
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    use hmac::{Hmac, Mac};
+
+    // HMAC takes keys of any length
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key).unwrap();
+    mac.update(data.as_bytes());
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(not(feature = "aws"))]
+fn sha256(_data: &[u8]) -> Vec<u8> {
+    missing_feature()
+}
+
+#[cfg(not(feature = "aws"))]
+fn hmac(_key: &[u8], _data: &str) -> Vec<u8> {
+    missing_feature()
+}
+
+#[cfg(not(feature = "aws"))]
+fn missing_feature() -> ! {
+    abort!(
+        Span::call_site(),
+        "Signing a request with AWS Signature Version 4 needs the `aws` feature";
+        help = "Add `features = [\"aws\"]` to the `http-macros` dependency"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uris() {
+        assert_eq!(
+            split_uri("https://example.com?a=1"),
+            (Some("example.com"), "/", "a=1")
+        );
+        assert_eq!(split_uri("/notes/1"), (None, "/notes/1", ""));
+    }
+
+    #[test]
+    fn query() {
+        assert_eq!(canonical_query("b=2&a=x y&a=%7E&c"), "a=x%20y&a=~&b=2&c=");
+    }
+
+    #[test]
+    fn dates() {
+        assert!(is_amz_date("20150830T123600Z"));
+        assert!(!is_amz_date("2015-08-30T12:36:00Z"));
+    }
+
+    #[test]
+    #[cfg(feature = "aws")]
+    fn signature() {
+        // The example of the AWS documentation for signing a request
+        let mut directives = Directives::default();
+        directives.push_line("# @aws-date 20150830T123600Z");
+        let mut headers = Vec::from([
+            ("Host".to_string(), "iam.amazonaws.com".to_string()),
+            (
+                "Content-Type".to_string(),
+                "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+            ),
+            (
+                "Authorization".to_string(),
+                "AWS AKIDEXAMPLE wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY region:us-east-1 service:iam".to_string(),
+            ),
+        ]);
+
+        let signer = sign(
+            "GET",
+            "/?Action=ListUsers&Version=2010-05-08",
+            &directives,
+            &mut headers,
+            &Body::default(),
+        );
+
+        assert_eq!(signer, None);
+        assert_eq!(
+            headers::get(&headers, "X-Amz-Date"),
+            Some("20150830T123600Z")
+        );
+        assert_eq!(
+            headers::get(&headers, "Authorization"),
+            Some("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7")
+        );
+    }
+}
@@ -0,0 +1,637 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
+
+use crate::{
+    body_encoding, charset, compression, directives::Directives, dynamic, form, graphql, headers,
+    json, multipart, schema, serialization, variables::Variables,
+};
+
+/// The marker line that starts a new chunk in a chunked body.
+const CHUNK_MARKER: &str = "chunk";
+
+/// The marker line that starts the trailer fields after the data.
+const TRAILERS_MARKER: &str = "trailers";
+
+/// The body of a request and the way it is framed.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Body {
+    /// The payload of the body.
+    pub data: Vec<u8>,
+
+    /// The size of each chunk when the body is sent with `Transfer-Encoding: chunked`.
+    pub chunks: Option<Vec<usize>>,
+
+    /// The trailer fields that are sent after the data.
+    pub trailers: Vec<(String, String)>,
+
+    /// Whether the data is bytes rather than text.
+    pub binary: bool,
+
+    /// Whether the text has dynamic variables which are only known when the request is made.
+    pub runtime: bool,
+}
+
+impl Body {
+    /// Creates the body from the part of the request after the headers.
+    /// Any files that are read to make the body are added to `files`.
+    /// A multipart body adds its boundary to the `Content-Type` header when it does not have one.
+    pub fn new(
+        buf: &[u8],
+        headers: &mut [(String, String)],
+        directives: &Directives,
+        variables: &Variables,
+        files: &mut Vec<String>,
+    ) -> Self {
+        // Only an included file can have bytes which are not UTF-8, and those are sent as they are
+        let Ok(text) = std::str::from_utf8(buf) else {
+            let data = compression::encode(buf.to_vec(), &compression::get_codings(headers));
+            let chunks = is_chunked(headers).then(|| auto_chunks(data.len(), directives));
+
+            return Self {
+                data,
+                chunks,
+                trailers: Vec::new(),
+                binary: true,
+                runtime: false,
+            };
+        };
+        let chunked = is_chunked(headers);
+        let markers: &[&str] = if chunked {
+            &[CHUNK_MARKER, TRAILERS_MARKER]
+        } else {
+            &[TRAILERS_MARKER]
+        };
+
+        let mut sections = split_sections(text, markers).into_iter().peekable();
+
+        // The body is either written as plain text or as explicit chunks
+        let (written, parts) = match sections.next_if(|(marker, _)| marker.is_none()) {
+            Some((_, content)) => (content.to_string(), None),
+            None => {
+                let mut parts = Vec::new();
+
+                while let Some((_, content)) =
+                    sections.next_if(|(marker, _)| *marker == Some(CHUNK_MARKER))
+                {
+                    if content.is_empty() {
+                        abort!(
+                            Span::call_site(),
+                            "Empty chunk";
+                            help = "An empty chunk marks the end of a chunked body. Remove the extra `--- chunk ---` line"
+                        );
+                    }
+
+                    parts.push(content);
+                }
+
+                (parts.concat(), Some(parts))
+            }
+        };
+
+        let trailers = match sections.next() {
+            Some((Some(TRAILERS_MARKER), content)) => {
+                let trailers = parse_fields(content);
+
+                for (name, value) in &trailers {
+                    headers::check_trailer(name, value);
+                }
+
+                trailers
+            }
+            Some((marker, _)) => abort!(
+                Span::call_site(),
+                "Unexpected `--- {} ---` line", marker.unwrap_or_default();
+                help = "A chunked body is either plain text or `--- chunk ---` sections, optionally followed by a `--- trailers ---` section"
+            ),
+            None => Vec::new(),
+        };
+
+        if sections.next().is_some() {
+            abort!(
+                Span::call_site(),
+                "Unexpected section after the trailers";
+                help = "The `--- trailers ---` section should be last"
+            );
+        }
+
+        let multipart = multipart::is_multipart(headers);
+        let serialization = serialization::get_format(headers);
+        let (body_encoding, encoded_text) = body_encoding::Encoding::new(directives, &written);
+
+        // These bodies are written in their own binary format, so they do not have a charset
+        let encoded = multipart || serialization.is_some() || body_encoding.is_some();
+        let charset = charset::get_charset(headers).filter(|_| !encoded);
+        let codings = compression::get_codings(headers);
+        let binary = encoded || charset.is_some() || !codings.is_empty();
+
+        // Explicit chunks split the body at exact places, so the body cannot be changed after that
+        if parts.is_some() && encoded {
+            abort!(
+                Span::call_site(),
+                "Explicit chunks cannot be used with multipart, MessagePack, CBOR or `# @body-encoding` bodies";
+                help = "Write the body as plain text and use `# @chunk-size` to split the encoded body into chunks"
+            );
+        }
+
+        if parts.is_some() && !codings.is_empty() {
+            abort!(
+                Span::call_site(),
+                "Explicit chunks cannot be used with `Content-Encoding`";
+                help = "Write the body as plain text and use `# @chunk-size` to split the encoded body into chunks"
+            );
+        }
+
+        let data = if let Some(encoding) = body_encoding {
+            body_encoding::decode(encoded_text, encoding)
+        } else if multipart {
+            multipart::encode(&written, headers, variables, files)
+        } else if let Some(format) = serialization {
+            serialization::encode(&written, format)
+        } else {
+            let content = rewrite(&written, headers, directives, files);
+
+            if parts.is_some() && content != written {
+                abort!(
+                    Span::call_site(),
+                    "Explicit chunks cannot be used when the body is rewritten";
+                    help = "Write the body as plain text and use `# @chunk-size` to split it into chunks"
+                );
+            }
+
+            charset::encode(&content, charset)
+        };
+        let data = compression::encode(data, &codings);
+
+        let chunks = match parts {
+            _ if !chunked => None,
+            // Each part is measured within the encoded body, so a byte order mark is only counted once
+            Some(parts) => {
+                let mut written = String::new();
+                let mut end = 0;
+
+                Some(
+                    parts
+                        .into_iter()
+                        .map(|part| {
+                            written.push_str(part);
+                            let start = end;
+                            end = charset::encode(&written, charset).len();
+
+                            end - start
+                        })
+                        .collect(),
+                )
+            }
+            None => Some(auto_chunks(data.len(), directives)),
+        };
+
+        Self {
+            data,
+            chunks,
+            trailers,
+            binary,
+            runtime: false,
+        }
+    }
+
+    /// Keeps the dynamic variables which are only known when the request is made, so that they are made with the request.
+    /// This only works for a text body which is sent as it is written, and whose length does not need to be known when the macro runs.
+    pub fn make_at_runtime(&mut self, headers: &[(String, String)], directives: &Directives) {
+        let kept = !self.binary && std::str::from_utf8(&self.data).is_ok_and(dynamic::is_runtime);
+        let content_length =
+            directives.has("content-length") || headers::get(headers, "Content-Length").is_some();
+
+        if !kept || self.chunks.is_some() || !self.trailers.is_empty() || content_length {
+            abort!(
+                Span::call_site(),
+                "The body has dynamic variables which are only known when the request is made";
+                help = "Only a text body without `Content-Length`, chunks or an encoding can be made with the request. Add a `# @random-seed 42` directive to make the random values when the macro runs"
+            );
+        }
+
+        self.runtime = true;
+    }
+
+    /// Returns the data of each chunk.
+    pub fn chunks(&self) -> Option<Vec<&[u8]>> {
+        let sizes = self.chunks.as_ref()?;
+        let mut rest = self.data.as_slice();
+
+        Some(
+            sizes
+                .iter()
+                .map(|size| {
+                    let (chunk, tail) = rest.split_at(*size);
+                    rest = tail;
+                    chunk
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the type of the body in the generated code.
+    pub fn type_tokens(&self) -> proc_macro2::TokenStream {
+        if self.chunks.is_some() || !self.trailers.is_empty() {
+            quote! { impl http_body::Body<Data = &'static [u8], Error = std::convert::Infallible> }
+        } else if self.binary {
+            quote! { Vec<u8> }
+        } else {
+            quote! { String }
+        }
+    }
+
+    /// Writes the body the way it is sent over the wire.
+    pub fn to_wire(&self, wire: &mut Vec<u8>) {
+        if self.runtime {
+            abort!(
+                Span::call_site(),
+                "The body is only known when the request is made";
+                help = "Use `request!` for requests with values that are made when the request is made"
+            );
+        }
+
+        let Some(chunks) = self.chunks() else {
+            if !self.trailers.is_empty() {
+                abort!(
+                    Span::call_site(),
+                    "Trailers can only be sent with `Transfer-Encoding: chunked` over the wire";
+                    help = "Add a `Transfer-Encoding: chunked` header"
+                );
+            }
+
+            wire.extend_from_slice(&self.data);
+            return;
+        };
+
+        for chunk in chunks {
+            wire.extend_from_slice(format!("{:X}\r\n", chunk.len()).as_bytes());
+            wire.extend_from_slice(chunk);
+            wire.extend_from_slice(b"\r\n");
+        }
+
+        wire.extend_from_slice(b"0\r\n");
+
+        for (name, value) in &self.trailers {
+            wire.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+
+        wire.extend_from_slice(b"\r\n");
+    }
+}
+
+impl ToTokens for Body {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let chunks = match self.chunks() {
+            Some(chunks) => chunks,
+            None if self.trailers.is_empty() && self.binary => {
+                let body = proc_macro2::Literal::byte_string(&self.data);
+
+                quote! { #body.to_vec() }.to_tokens(tokens);
+                return;
+            }
+            None if self.trailers.is_empty() => {
+                // Safe to unwrap since the TokenStream already makes sure it is a valid UTF-8 string
+                let body = String::from_utf8(self.data.clone()).unwrap();
+
+                if self.runtime {
+                    dynamic::value_tokens(&body).to_tokens(tokens);
+                } else {
+                    quote! { #body.to_string() }.to_tokens(tokens);
+                }
+
+                return;
+            }
+            // The trailers need their own frame, so the data is a single frame before them
+            None if self.data.is_empty() => Vec::new(),
+            None => Vec::from([self.data.as_slice()]),
+        };
+
+        let chunks = chunks
+            .into_iter()
+            .map(proc_macro2::Literal::byte_string)
+            .map(|chunk| quote! { frames.push_back(http_body::Frame::data(&#chunk[..])); });
+
+        let trailers = (!self.trailers.is_empty()).then(|| {
+            let fields = self.trailers.iter().map(|(name, value)| {
+                let name = name.to_lowercase();
+
+                quote! {
+                    trailers.append(
+                        http::header::HeaderName::from_static(#name),
+                        http::header::HeaderValue::from_static(#value),
+                    );
+                }
+            });
+
+            quote! {
+                let mut trailers = http::HeaderMap::new();
+                #(#fields)*
+                frames.push_back(http_body::Frame::trailers(trailers));
+            }
+        });
+
+        let body = quote! {
+            {
+                struct FramedBody {
+                    frames: std::collections::VecDeque<http_body::Frame<&'static [u8]>>,
+                }
+
+                impl http_body::Body for FramedBody {
+                    type Data = &'static [u8];
+                    type Error = std::convert::Infallible;
+
+                    fn poll_frame(
+                        self: std::pin::Pin<&mut Self>,
+                        _cx: &mut std::task::Context<'_>,
+                    ) -> std::task::Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+                        std::task::Poll::Ready(self.get_mut().frames.pop_front().map(Ok))
+                    }
+
+                    fn is_end_stream(&self) -> bool {
+                        self.frames.is_empty()
+                    }
+                }
+
+                let mut frames = std::collections::VecDeque::new();
+                #(#chunks)*
+                #trailers
+
+                FramedBody { frames }
+            }
+        };
+
+        body.to_tokens(tokens);
+    }
+}
+
+/// Rewrites the body as it is written in the macro to the text that is sent.
+fn rewrite(
+    written: &str,
+    headers: &[(String, String)],
+    directives: &Directives,
+    files: &mut Vec<String>,
+) -> String {
+    // Dynamic variables which are made with the request are numbers while the JSON is checked
+    let (mut content, placeholders) = match json::is_json(headers) {
+        true => dynamic::placeholders(written),
+        false => (written.to_string(), Vec::new()),
+    };
+
+    if graphql::is_graphql(headers) {
+        content = graphql::wrap(&content, directives, files);
+    } else if graphql::is_query(headers) {
+        graphql::check(&content, directives, files);
+    }
+
+    if json::is_json(headers) {
+        content = json::process(&content, json::Format::new(directives), directives);
+    }
+
+    if form::is_form(headers) {
+        content = form::encode(&content);
+    }
+
+    if let Some(path) = directives.get("schema") {
+        if !json::is_json(headers) {
+            abort!(
+                Span::call_site(),
+                "`# @schema` can only be used with JSON bodies";
+                help = "Add a `Content-Type: application/json` header"
+            );
+        }
+
+        files.push(schema::validate(&content, path));
+    }
+
+    dynamic::restore(content, &placeholders)
+}
+
+/// Returns whether the headers ask for the body to be sent with chunked transfer encoding.
+pub fn is_chunked(headers: &[(String, String)]) -> bool {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Transfer-Encoding"))
+        .flat_map(|(_, value)| value.split(','))
+        .last()
+        .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+}
+
+/// Gets the chunk sizes for a body that is split up automatically.
+/// The chunk size comes from `# @chunk-size` with the whole body being a single chunk by default.
+fn auto_chunks(len: usize, directives: &Directives) -> Vec<usize> {
+    let size = match directives.get("chunk-size") {
+        Some(size) => match size.parse::<usize>() {
+            Ok(size) if size > 0 => size,
+            _ => abort!(
+                Span::call_site(),
+                "Invalid chunk size `{}`", size;
+                help = "Try `# @chunk-size 16`"
+            ),
+        },
+        None => len.max(1),
+    };
+
+    (0..len)
+        .step_by(size)
+        .map(|start| size.min(len - start))
+        .collect()
+}
+
+/// Splits a body on `--- name ---` marker lines for the given marker names.
+/// The content before the first marker has no name. And the new line before a marker is part of the marker.
+fn split_sections<'a>(text: &'a str, markers: &[&str]) -> Vec<(Option<&'a str>, &'a str)> {
+    let mut sections = Vec::new();
+    let mut marker = None;
+    let mut start = 0;
+    let mut line_start: usize = 0;
+
+    for line in text.split_inclusive('\n') {
+        let next_marker = line
+            .trim()
+            .strip_prefix("--- ")
+            .and_then(|line| line.strip_suffix(" ---"))
+            .filter(|name| markers.contains(name));
+
+        if let Some(next_marker) = next_marker {
+            let end = line_start.saturating_sub(1).max(start);
+
+            if marker.is_some() || start != line_start {
+                sections.push((marker, &text[start..end]));
+            }
+
+            marker = Some(next_marker);
+            start = line_start + line.len();
+        }
+
+        line_start += line.len();
+    }
+
+    if marker.is_some() || start != text.len() {
+        sections.push((marker, &text[start..]));
+    }
+
+    sections
+}
+
+/// Parses `Name: value` lines into fields.
+fn parse_fields(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let Some((name, value)) = line.split_once(':') else {
+                abort!(
+                    Span::call_site(),
+                    "Invalid field `{}`", line;
+                    help = "Fields should look like `Name: value`"
+                );
+            };
+
+            (name.trim().to_string(), value.trim().to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(value: &str) -> Vec<(String, String)> {
+        Vec::from([("Transfer-Encoding".to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn chunked() {
+        assert!(is_chunked(&headers("chunked")));
+        assert!(is_chunked(&headers("gzip, Chunked")));
+        assert!(!is_chunked(&headers("chunked, gzip")));
+        assert!(!is_chunked(&[]));
+    }
+
+    #[test]
+    fn sections() {
+        let markers = &[CHUNK_MARKER, TRAILERS_MARKER];
+
+        assert_eq!(
+            split_sections("Hello", markers),
+            Vec::from([(None, "Hello")])
+        );
+        assert_eq!(
+            split_sections(
+                "--- chunk ---\nHello\n--- chunk ---\nWorld\n\n--- trailers ---\nA: b\n",
+                markers
+            ),
+            Vec::from([
+                (Some("chunk"), "Hello"),
+                (Some("chunk"), "World\n"),
+                (Some("trailers"), "A: b\n"),
+            ])
+        );
+        assert_eq!(
+            split_sections("Hello\n--- trailers ---", markers),
+            Vec::from([(None, "Hello"), (Some("trailers"), "")])
+        );
+        assert_eq!(
+            split_sections("Hello\n--- other ---", markers),
+            Vec::from([(None, "Hello\n--- other ---")])
+        );
+    }
+
+    #[test]
+    fn not_chunked() {
+        let actual = Body::new(
+            b"--- chunk ---\nHello",
+            &mut [],
+            &Directives::default(),
+            &Variables::default(),
+            &mut Vec::new(),
+        );
+        let expected = Body {
+            data: b"--- chunk ---\nHello".to_vec(),
+            ..Default::default()
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn trailers() {
+        let actual = Body::new(
+            b"Hello\n--- chunk ---\n--- trailers ---\nGrpc-Status: 0",
+            &mut [],
+            &Directives::default(),
+            &Variables::default(),
+            &mut Vec::new(),
+        );
+        let expected = Body {
+            data: b"Hello\n--- chunk ---".to_vec(),
+            chunks: None,
+            trailers: Vec::from([("Grpc-Status".to_string(), "0".to_string())]),
+            binary: false,
+            runtime: false,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn auto() {
+        let mut directives = Directives::default();
+        directives.push_line("# @chunk-size 4");
+
+        let actual = Body::new(
+            b"Hello World",
+            &mut headers("chunked"),
+            &directives,
+            &Variables::default(),
+            &mut Vec::new(),
+        );
+        let expected = Body {
+            data: b"Hello World".to_vec(),
+            chunks: Some(Vec::from([4, 4, 3])),
+            trailers: Vec::new(),
+            binary: false,
+            runtime: false,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn explicit() {
+        let actual = Body::new(
+            b"--- chunk ---\nHello\n--- chunk ---\n World\n--- trailers ---\nExpires: never\n",
+            &mut headers("chunked"),
+            &Directives::default(),
+            &Variables::default(),
+            &mut Vec::new(),
+        );
+        let expected = Body {
+            data: b"Hello World".to_vec(),
+            chunks: Some(Vec::from([5, 6])),
+            trailers: Vec::from([("Expires".to_string(), "never".to_string())]),
+            binary: false,
+            runtime: false,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn wire() {
+        let body = Body {
+            data: b"Hello World".to_vec(),
+            chunks: Some(Vec::from([5, 6])),
+            trailers: Vec::from([("Expires".to_string(), "never".to_string())]),
+            binary: false,
+            runtime: false,
+        };
+        let mut wire = Vec::new();
+        body.to_wire(&mut wire);
+
+        assert_eq!(
+            wire,
+            b"5\r\nHello\r\n6\r\n World\r\n0\r\nExpires: never\r\n\r\n".to_vec()
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use base64::Engine;
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::directives::Directives;
+
+/// How a binary body is written in the request.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+    Base64,
+    Hex,
+}
+
+impl Encoding {
+    /// Gets the encoding from a `# @body-encoding` line at the start of the body, or else from the `# @body-encoding` directive.
+    /// Returns the body without the marker line.
+    pub fn new<'a>(directives: &Directives, body: &'a str) -> (Option<Self>, &'a str) {
+        let (first, rest) = body.split_once('\n').unwrap_or((body, ""));
+        let marker = first
+            .strip_prefix('#')
+            .and_then(|comment| comment.trim().strip_prefix("@body-encoding"));
+
+        match marker {
+            Some(value) => (Some(Self::parse(value.trim())), rest),
+            None => (directives.get("body-encoding").map(Self::parse), body),
+        }
+    }
+
+    /// Parses the value of a `# @body-encoding` line.
+    fn parse(value: &str) -> Self {
+        match value {
+            "base64" => Self::Base64,
+            "hex" => Self::Hex,
+            other => abort!(
+                Span::call_site(),
+                "Unknown body encoding `{}`", other;
+                help = "Try `# @body-encoding base64` or `# @body-encoding hex`"
+            ),
+        }
+    }
+}
+
+/// Decodes the body, which can be wrapped over many lines.
+pub fn decode(text: &str, encoding: Encoding) -> Vec<u8> {
+    let data: String = text.split_whitespace().collect();
+
+    match encoding {
+        Encoding::Base64 => base64::engine::general_purpose::STANDARD
+            .decode(&data)
+            .unwrap_or_else(|error| {
+                abort!(
+                    Span::call_site(),
+                    "Invalid base64 body: {}", error;
+                    help = "The offset does not count the whitespace between the lines"
+                )
+            }),
+        Encoding::Hex => {
+            let digits: Vec<u8> = data
+                .chars()
+                .map(|c| match c.to_digit(16) {
+                    Some(digit) => digit as u8,
+                    None => abort!(
+                        Span::call_site(),
+                        "Invalid hex body: `{}` is not a hex digit", c;
+                        help = "Each byte is written as two hex digits, like `0a`"
+                    ),
+                })
+                .collect();
+
+            if !digits.len().is_multiple_of(2) {
+                abort!(
+                    Span::call_site(),
+                    "Invalid hex body: it has an odd number of digits";
+                    help = "Each byte is written as two hex digits, like `0a`"
+                );
+            }
+
+            digits
+                .chunks(2)
+                .map(|pair| pair[0] << 4 | pair[1])
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings() {
+        let mut directives = Directives::default();
+        assert_eq!(Encoding::new(&directives, "0a"), (None, "0a"));
+        assert_eq!(
+            Encoding::new(&directives, "# @body-encoding base64\nCg=="),
+            (Some(Encoding::Base64), "Cg==")
+        );
+
+        directives.push_line("# @body-encoding hex");
+        assert_eq!(
+            Encoding::new(&directives, "0a"),
+            (Some(Encoding::Hex), "0a")
+        );
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(
+            decode("SGVsbG8g\nV29ybGQ=", Encoding::Base64),
+            b"Hello World"
+        );
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(decode("0a 1B\nff", Encoding::Hex), b"\x0a\x1b\xff");
+    }
+}
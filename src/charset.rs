@@ -0,0 +1,190 @@
+use encoding_rs::Encoding;
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::headers;
+
+/// The labels of ISO-8859-1, which the WHATWG Encoding Standard treats as windows-1252.
+const LATIN1_LABELS: &[&str] = &[
+    "cp819",
+    "csisolatin1",
+    "ibm819",
+    "iso-8859-1",
+    "iso-ir-100",
+    "iso8859-1",
+    "iso88591",
+    "iso_8859-1",
+    "iso_8859-1:1987",
+    "l1",
+    "latin1",
+];
+
+/// The charsets the body can be encoded in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Charset {
+    /// Any charset known to the WHATWG Encoding Standard (other than UTF-8, UTF-16 and ISO-8859-1).
+    Whatwg(&'static Encoding),
+    Ascii,
+    /// ISO-8859-1, which has the first 256 code points of Unicode.
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+    /// UTF-16 without an explicit byte order, which is big endian with a byte order mark.
+    Utf16,
+}
+
+/// Gets the charset from the `charset` parameter of the `Content-Type` header.
+/// Returns `None` when there is no charset or when it is UTF-8, since the body is already in UTF-8.
+pub fn get_charset(headers: &[(String, String)]) -> Option<Charset> {
+    let label = headers::content_type_parameter(headers, "charset")?;
+
+    let label = label.to_lowercase();
+
+    let charset = match label.as_str() {
+        "us-ascii" | "ascii" => Charset::Ascii,
+        "utf-16" => Charset::Utf16,
+        label if LATIN1_LABELS.contains(&label) => Charset::Latin1,
+        _ => match Encoding::for_label(label.as_bytes()) {
+            Some(encoding) if encoding == encoding_rs::UTF_8 => return None,
+            Some(encoding) if encoding == encoding_rs::UTF_16LE => Charset::Utf16Le,
+            Some(encoding) if encoding == encoding_rs::UTF_16BE => Charset::Utf16Be,
+            // Like the `replacement` encoding, which can only decode
+            Some(encoding) if encoding.output_encoding() != encoding => abort!(
+                Span::call_site(),
+                "The body cannot be encoded in `{}`", label;
+                help = "Try `charset=UTF-8`, `charset=ISO-8859-1` or `charset=UTF-16LE`"
+            ),
+            Some(encoding) => Charset::Whatwg(encoding),
+            None => abort!(
+                Span::call_site(),
+                "Unknown charset `{}`", label;
+                help = "Try `charset=UTF-8`, `charset=ISO-8859-1` or `charset=UTF-16LE`"
+            ),
+        },
+    };
+
+    Some(charset)
+}
+
+/// Encodes the text in the charset.
+pub fn encode(text: &str, charset: Option<Charset>) -> Vec<u8> {
+    let Some(charset) = charset else {
+        return text.as_bytes().to_vec();
+    };
+
+    match charset {
+        Charset::Whatwg(encoding) => {
+            let (bytes, _, had_errors) = encoding.encode(text);
+
+            if had_errors {
+                unrepresentable(text, encoding.name(), |c| {
+                    encoding.encode(c.encode_utf8(&mut [0; 4])).2
+                });
+            }
+
+            bytes.into_owned()
+        }
+        Charset::Ascii => {
+            if !text.is_ascii() {
+                unrepresentable(text, "US-ASCII", |c| !c.is_ascii());
+            }
+
+            text.as_bytes().to_vec()
+        }
+        Charset::Latin1 => {
+            if text.chars().any(|c| u8::try_from(c).is_err()) {
+                unrepresentable(text, "ISO-8859-1", |c| u8::try_from(c).is_err());
+            }
+
+            text.chars().map(|c| c as u8).collect()
+        }
+        Charset::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        Charset::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        Charset::Utf16 => std::iter::once(0xFEFF)
+            .chain(text.encode_utf16())
+            .flat_map(u16::to_be_bytes)
+            .collect(),
+    }
+}
+
+/// Aborts on the first character that cannot be represented in the charset.
+fn unrepresentable(text: &str, charset: &str, is_unrepresentable: impl Fn(char) -> bool) -> ! {
+    let (line, column, c) = text
+        .lines()
+        .enumerate()
+        .flat_map(|(line, content)| {
+            content
+                .chars()
+                .enumerate()
+                .map(move |(column, c)| (line + 1, column + 1, c))
+        })
+        .find(|(_, _, c)| is_unrepresentable(*c))
+        .expect("the encoder had errors, so at least one character is unrepresentable");
+
+    abort!(
+        Span::call_site(),
+        "`{}` at line {} column {} of the body cannot be encoded in {}", c, line, column, charset;
+        help = "Use a charset which can represent it, like `charset=UTF-8`"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content_type(value: &str) -> Vec<(String, String)> {
+        Vec::from([("Content-Type".to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn charsets() {
+        assert_eq!(
+            get_charset(&content_type("text/plain; charset=ISO-8859-1")),
+            Some(Charset::Latin1)
+        );
+        assert_eq!(
+            get_charset(&content_type("text/plain; charset=windows-1252")),
+            Some(Charset::Whatwg(encoding_rs::WINDOWS_1252))
+        );
+        assert_eq!(
+            get_charset(&content_type("text/plain; charset=ucs-2")),
+            Some(Charset::Utf16Le)
+        );
+        assert_eq!(
+            get_charset(&content_type("text/plain; charset=unicode")),
+            Some(Charset::Utf16Le)
+        );
+        assert_eq!(
+            get_charset(&content_type("text/plain; charset=unicodeFFFE")),
+            Some(Charset::Utf16Be)
+        );
+        assert_eq!(
+            get_charset(&content_type("text/plain;charset=\"utf-16le\"")),
+            Some(Charset::Utf16Le)
+        );
+        assert_eq!(
+            get_charset(&content_type("text/plain; charset=utf-8")),
+            None
+        );
+        assert_eq!(get_charset(&content_type("application/json")), None);
+        assert_eq!(get_charset(&[]), None);
+    }
+
+    #[test]
+    fn encodings() {
+        assert_eq!(
+            encode("café", Some(Charset::Whatwg(encoding_rs::WINDOWS_1252))),
+            b"caf\xe9"
+        );
+        assert_eq!(encode("café ÿ", Some(Charset::Latin1)), b"caf\xe9 \xff");
+        assert_eq!(
+            encode("€", Some(Charset::Whatwg(encoding_rs::WINDOWS_1252))),
+            b"\x80"
+        );
+        assert_eq!(encode("hé", Some(Charset::Utf16Le)), b"h\0\xe9\0");
+        assert_eq!(encode("hé", Some(Charset::Utf16Be)), b"\0h\0\xe9");
+        assert_eq!(encode("h", Some(Charset::Utf16)), b"\xfe\xff\0h");
+        assert_eq!(encode("hi", Some(Charset::Ascii)), b"hi");
+        assert_eq!(encode("café", None), "café".as_bytes());
+    }
+}
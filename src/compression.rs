@@ -0,0 +1,130 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+/// Returns the content codings from the `Content-Encoding` headers in the order they are applied.
+pub fn get_codings(headers: &[(String, String)]) -> Vec<String> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Content-Encoding"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|coding| coding.trim().to_lowercase())
+        .filter(|coding| !coding.is_empty() && coding != "identity")
+        .collect()
+}
+
+/// Compresses the body with each of the content codings in turn.
+pub fn encode(mut data: Vec<u8>, codings: &[String]) -> Vec<u8> {
+    for coding in codings {
+        data = match coding.as_str() {
+            "gzip" | "x-gzip" => gzip(&data),
+            "deflate" => deflate(&data),
+            "br" => br(&data),
+            "zstd" => zstd(&data),
+            _ => abort!(
+                Span::call_site(),
+                "Unsupported content coding `{}`", coding;
+                help = "Supported content codings are: gzip, deflate, br, zstd"
+            ),
+        };
+    }
+
+    data
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip(_data: &[u8]) -> Vec<u8> {
+    missing_feature("gzip")
+}
+
+#[cfg(feature = "deflate")]
+fn deflate(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    // The `deflate` content coding is actually the zlib format
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(not(feature = "deflate"))]
+fn deflate(_data: &[u8]) -> Vec<u8> {
+    missing_feature("deflate")
+}
+
+#[cfg(feature = "br")]
+fn br(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+    encoder.write_all(data).unwrap();
+    encoder.into_inner()
+}
+
+#[cfg(not(feature = "br"))]
+fn br(_data: &[u8]) -> Vec<u8> {
+    missing_feature("br")
+}
+
+#[cfg(feature = "zstd")]
+fn zstd(data: &[u8]) -> Vec<u8> {
+    zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL).unwrap()
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd(_data: &[u8]) -> Vec<u8> {
+    missing_feature("zstd")
+}
+
+/// Aborts because the feature for a content coding is not turned on.
+#[cfg(not(all(
+    feature = "gzip",
+    feature = "deflate",
+    feature = "br",
+    feature = "zstd"
+)))]
+fn missing_feature(feature: &str) -> ! {
+    abort!(
+        Span::call_site(),
+        "`Content-Encoding: {}` needs the `{}` feature", feature, feature;
+        help = "Add `features = [\"{}\"]` to the `http-macros` dependency", feature
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codings() {
+        let headers = Vec::from([
+            ("Content-Encoding".to_string(), "identity, GZIP".to_string()),
+            ("content-encoding".to_string(), "br".to_string()),
+        ]);
+
+        assert_eq!(get_codings(&headers), ["gzip", "br"]);
+        assert_eq!(get_codings(&[]), Vec::<String>::new());
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn gzip() {
+        use std::io::Read;
+
+        let encoded = encode(b"Hello".to_vec(), &["gzip".to_string()]);
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(encoded.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+
+        assert_eq!(decoded, "Hello");
+    }
+}
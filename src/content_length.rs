@@ -0,0 +1,108 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::{body::Body, directives::Directives};
+
+/// Checks an explicit `Content-Length` header against the body. Or sets it when it is missing and
+/// `# @content-length` was given.
+pub fn apply(directives: &Directives, headers: &mut Vec<(String, String)>, body: &Body) {
+    let length = body.data.len();
+
+    let explicit = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("Content-Length"));
+
+    match explicit {
+        Some((_, value)) => {
+            if directives.allows("content-length-mismatch") {
+                return;
+            }
+
+            if body.chunks.is_some() {
+                abort!(
+                    Span::call_site(),
+                    "`Content-Length` cannot be used with `Transfer-Encoding: chunked`";
+                    help = "Remove the `Content-Length` header or add `# @allow content-length-mismatch` if it is on purpose"
+                );
+            }
+
+            if value.parse::<usize>().ok() != Some(length) {
+                abort!(
+                    Span::call_site(),
+                    "`Content-Length: {}` does not match the body of {} bytes", value, length;
+                    help = "Use `Content-Length: {}` or add `# @allow content-length-mismatch` if the mismatch is on purpose", length
+                );
+            }
+        }
+        None if directives.has("content-length") && body.chunks.is_none() => {
+            headers.push(("Content-Length".to_string(), length.to_string()));
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(data: &[u8]) -> Body {
+        Body {
+            data: data.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn auto() {
+        let mut directives = Directives::default();
+        directives.push_line("# @content-length");
+        let mut headers = Vec::from([("Host".to_string(), "example.com".to_string())]);
+
+        apply(&directives, &mut headers, &body(b"Hello"));
+
+        assert_eq!(
+            headers,
+            Vec::from([
+                ("Host".to_string(), "example.com".to_string()),
+                ("Content-Length".to_string(), "5".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn explicit() {
+        let mut headers = Vec::from([("content-length".to_string(), "5".to_string())]);
+
+        apply(&Directives::default(), &mut headers, &body(b"Hello"));
+
+        assert_eq!(
+            headers,
+            Vec::from([("content-length".to_string(), "5".to_string())])
+        );
+    }
+
+    #[test]
+    fn not_set() {
+        let mut headers = Vec::new();
+
+        apply(&Directives::default(), &mut headers, &body(b"Hello"));
+
+        assert_eq!(headers, Vec::new());
+    }
+
+    #[test]
+    fn chunked() {
+        let mut directives = Directives::default();
+        directives.push_line("# @content-length");
+        let mut headers = Vec::new();
+        let body = Body {
+            data: b"Hello".to_vec(),
+            chunks: Some(Vec::from([5])),
+            ..Default::default()
+        };
+
+        apply(&directives, &mut headers, &body);
+
+        assert_eq!(headers, Vec::new());
+    }
+}
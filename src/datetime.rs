@@ -0,0 +1,228 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::dynamic::Part;
+
+const OFFSET_HELP: &str =
+    "Offsets are a number and a unit, like `-3 h`. The units are y, M, w, d, h, m, s and ms";
+
+/// The dayjs tokens REST Client uses in custom formats, with the matching `chrono` specifiers.
+/// Longer tokens come first so that `MMMM` is not read as `MM` twice.
+const FORMAT_TOKENS: &[(&str, &str)] = &[
+    ("YYYY", "%Y"),
+    ("YY", "%y"),
+    ("MMMM", "%B"),
+    ("MMM", "%b"),
+    ("MM", "%m"),
+    ("M", "%-m"),
+    ("DD", "%d"),
+    ("D", "%-d"),
+    ("dddd", "%A"),
+    ("ddd", "%a"),
+    ("HH", "%H"),
+    ("H", "%-H"),
+    ("hh", "%I"),
+    ("h", "%-I"),
+    ("mm", "%M"),
+    ("m", "%-M"),
+    ("ss", "%S"),
+    ("s", "%-S"),
+    ("SSS", "%3f"),
+    ("A", "%p"),
+    ("a", "%P"),
+    ("ZZ", "%z"),
+    ("Z", "%:z"),
+    ("X", "%s"),
+];
+
+/// An offset from the current time.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Offset {
+    /// Years and months, which do not have a fixed length.
+    Months(i64),
+    Milliseconds(i64),
+}
+
+/// Writes the code for the seconds since 1970 of `{{$timestamp}}` or `{{$timestamp -3 h}}`.
+/// Only offsets in years or months need `chrono`, the others are added to the `SystemTime`.
+pub fn timestamp(arguments: &str) -> Part {
+    let milliseconds = match parse_offset(arguments) {
+        None => 0,
+        Some(Offset::Milliseconds(milliseconds)) => milliseconds,
+        months @ Some(Offset::Months(_)) => {
+            let now = now(months, false);
+            return Part::Code(quote! { #now.timestamp().to_string() });
+        }
+    };
+
+    Part::Code(quote! {
+        {
+            let now = ::std::time::SystemTime::now()
+                .duration_since(::std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as i64;
+
+            (now + #milliseconds).div_euclid(1000).to_string()
+        }
+    })
+}
+
+/// Writes the code for `{{$datetime rfc1123|iso8601|"format" [offset]}}`, or `{{$localDatetime ...}}` when `local`.
+pub fn datetime(arguments: &str, local: bool) -> Part {
+    let name = if local { "localDatetime" } else { "datetime" };
+
+    let (format, rest) = match arguments.chars().next() {
+        Some(quote @ ('"' | '\'')) => match arguments[1..].split_once(quote) {
+            Some((format, rest)) => (convert_format(format), rest),
+            None => abort!(
+                Span::call_site(),
+                "Unterminated format `{}` of `${}`", arguments, name;
+                help = "End the format with {}", quote
+            ),
+        },
+        _ => {
+            let (format, rest) = arguments
+                .split_once(char::is_whitespace)
+                .unwrap_or((arguments, ""));
+            let format = match (format, local) {
+                ("rfc1123", false) => "%a, %d %b %Y %H:%M:%S GMT",
+                ("rfc1123", true) => "%a, %d %b %Y %H:%M:%S %z",
+                ("iso8601", false) => "%Y-%m-%dT%H:%M:%S%.3fZ",
+                ("iso8601", true) => "%Y-%m-%dT%H:%M:%S%.3f%:z",
+                (other, _) => abort!(
+                    Span::call_site(),
+                    "Unknown format `{}` of `${}`", other, name;
+                    help = "Try `{{${} iso8601}}`, `{{${} rfc1123}}` or a format like `{{${} \"YYYY-MM-DD\"}}`", name, name, name
+                ),
+            };
+
+            (format.to_string(), rest)
+        }
+    };
+
+    let now = now(parse_offset(rest), local);
+
+    Part::Code(quote! { #now.format(#format).to_string() })
+}
+
+/// Writes the code for the current time with the offset.
+fn now(offset: Option<Offset>, local: bool) -> proc_macro2::TokenStream {
+    let now = match local {
+        true => quote! { ::chrono::Local::now() },
+        false => quote! { ::chrono::Utc::now() },
+    };
+
+    match offset {
+        None => now,
+        Some(Offset::Months(months)) => {
+            let count = months.unsigned_abs() as u32;
+            let method = match months < 0 {
+                true => quote! { checked_sub_months },
+                false => quote! { checked_add_months },
+            };
+
+            quote! { #now.#method(::chrono::Months::new(#count)).unwrap() }
+        }
+        Some(Offset::Milliseconds(milliseconds)) => {
+            quote! { (#now + ::chrono::Duration::milliseconds(#milliseconds)) }
+        }
+    }
+}
+
+/// Parses an offset like `-3 h` or `1 y`.
+fn parse_offset(text: &str) -> Option<Offset> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let split = text
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| !c.is_ascii_digit())
+        .map_or(text.len(), |(index, _)| index);
+    let (amount, unit) = text.split_at(split);
+
+    let Ok(amount) = amount.parse::<i64>() else {
+        abort!(
+            Span::call_site(),
+            "Invalid offset `{}`", text;
+            help = "{}", OFFSET_HELP
+        );
+    };
+
+    let offset = match unit.trim() {
+        "y" => Offset::Months(amount * 12),
+        "M" => Offset::Months(amount),
+        "w" => Offset::Milliseconds(amount * 7 * 24 * 60 * 60 * 1000),
+        "d" => Offset::Milliseconds(amount * 24 * 60 * 60 * 1000),
+        "h" => Offset::Milliseconds(amount * 60 * 60 * 1000),
+        "m" => Offset::Milliseconds(amount * 60 * 1000),
+        "s" => Offset::Milliseconds(amount * 1000),
+        "ms" => Offset::Milliseconds(amount),
+        other => abort!(
+            Span::call_site(),
+            "Unknown offset unit `{}`", other;
+            help = "{}", OFFSET_HELP
+        ),
+    };
+
+    Some(offset)
+}
+
+/// Converts a dayjs format, like `DD-MM-YYYY`, to a `chrono` format.
+/// Text in `[brackets]` is kept as it is.
+fn convert_format(format: &str) -> String {
+    let mut converted = String::new();
+    let mut rest = format;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((literal, after)) = rest.strip_prefix('[').and_then(|rest| rest.split_once(']'))
+        {
+            converted.push_str(&literal.replace('%', "%%"));
+            rest = after;
+        } else if let Some((token, specifier)) = FORMAT_TOKENS
+            .iter()
+            .find(|(token, _)| rest.starts_with(token))
+        {
+            converted.push_str(specifier);
+            rest = &rest[token.len()..];
+        } else {
+            match c {
+                '%' => converted.push_str("%%"),
+                c => converted.push(c),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_offset(""), None);
+        assert_eq!(
+            parse_offset("-3 h"),
+            Some(Offset::Milliseconds(-10_800_000))
+        );
+        assert_eq!(parse_offset("1 y"), Some(Offset::Months(12)));
+        assert_eq!(parse_offset("2M"), Some(Offset::Months(2)));
+        assert_eq!(parse_offset("500 ms"), Some(Offset::Milliseconds(500)));
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(convert_format("DD-MM-YYYY"), "%d-%m-%Y");
+        assert_eq!(
+            convert_format("dddd, MMMM D [at] HH:mm:ss.SSS Z"),
+            "%A, %B %-d at %H:%M:%S.%3f %:z"
+        );
+        assert_eq!(convert_format("100%"), "100%%");
+    }
+}
@@ -0,0 +1,107 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+/// All the directives that can be given with `# @name value` before the request line.
+const KNOWN_DIRECTIVES: &[&str] = &[
+    "allow",
+    "aws-date",
+    "body-encoding",
+    "chunk-size",
+    "content-length",
+    "environment",
+    "graphql-schema",
+    "json",
+    "openapi",
+    "random-seed",
+    "resolve-env",
+    "schema",
+];
+
+/// All the checks that can be turned off with `# @allow name`.
+const KNOWN_LINTS: &[&str] = &[
+    "content-length-mismatch",
+    "duplicate-header",
+    "invalid-graphql",
+    "invalid-json",
+    "openapi-mismatch",
+    "undefined-variable",
+];
+
+/// The directives given in the comment lines before the request line.
+/// A directive looks like `# @name value` and is used to opt into (or out of) extra behaviour.
+/// Plain comments (without the `@`) are ignored.
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
+pub struct Directives(Vec<(String, String)>);
+
+impl Directives {
+    /// Parses the directive from a single comment line. Plain comments are skipped.
+    pub fn push_line(&mut self, line: &str) {
+        let comment = line.trim_start_matches('#').trim();
+
+        let Some(directive) = comment.strip_prefix('@') else {
+            return;
+        };
+
+        let (name, value) = directive.split_once(' ').unwrap_or((directive, ""));
+
+        if !KNOWN_DIRECTIVES.contains(&name) {
+            abort!(
+                Span::call_site(),
+                "Unknown directive `@{}`", name;
+                help = "Valid directives are: {}", KNOWN_DIRECTIVES.join(", ")
+            );
+        }
+
+        if name == "allow" && !KNOWN_LINTS.contains(&value.trim()) {
+            abort!(
+                Span::call_site(),
+                "Unknown lint `{}`", value.trim();
+                help = "Lints that can be allowed are: {}", KNOWN_LINTS.join(", ")
+            );
+        }
+
+        self.0.push((name.to_string(), value.trim().to_string()));
+    }
+
+    /// Returns the value of a directive if it was given.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns whether the directive was given.
+    pub fn has(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns whether the lint was turned off with `# @allow`.
+    pub fn allows(&self, lint: &str) -> bool {
+        self.0.iter().any(|(n, v)| n == "allow" && v == lint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_line() {
+        let mut directives = Directives::default();
+        directives.push_line("# A plain comment");
+        directives.push_line("# @content-length");
+        directives.push_line("# @allow content-length-mismatch");
+
+        assert_eq!(
+            directives,
+            Directives(Vec::from([
+                ("content-length".to_string(), "".to_string()),
+                ("allow".to_string(), "content-length-mismatch".to_string()),
+            ]))
+        );
+        assert!(directives.has("content-length"));
+        assert!(directives.allows("content-length-mismatch"));
+        assert!(!directives.has("other"));
+    }
+}
@@ -0,0 +1,287 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::{
+    datetime,
+    directives::Directives,
+    env, jwt,
+    random::{self, Seeded},
+};
+
+/// A part of a value with `{{$name arguments}}` dynamic variables.
+#[derive(Debug)]
+pub enum Part {
+    /// Text which is known when the macro runs.
+    Text(String),
+    /// Code giving a `String` which is only known when the request is made.
+    Code(proc_macro2::TokenStream),
+}
+
+/// What the dynamic variables need to be worked out.
+struct Context<'a> {
+    seeded: Option<Seeded>,
+    runtime_env: bool,
+    files: &'a mut Vec<String>,
+    env: &'a mut Vec<String>,
+}
+
+/// Works out the dynamic variables in the URI, header values and text body which are known when the macro runs.
+/// Variables which are only known when the request is made are kept for [value_tokens].
+/// The files and environment variables that are read are added to `files` and `env`.
+pub fn resolve(
+    uri: &mut String,
+    headers: &mut [(String, String)],
+    body: Option<&mut String>,
+    directives: &Directives,
+    files: &mut Vec<String>,
+    env: &mut Vec<String>,
+) {
+    let mut context = Context {
+        seeded: Seeded::new(directives),
+        runtime_env: env::at_runtime(directives),
+        files,
+        env,
+    };
+    let values = std::iter::once(uri)
+        .chain(headers.iter_mut().map(|(_, value)| value))
+        .chain(body);
+
+    for value in values {
+        let mut resolved = String::with_capacity(value.len());
+        let mut rest = value.as_str();
+
+        while let Some(variable) = find(rest) {
+            resolved.push_str(&rest[..variable.start]);
+
+            match evaluate(variable.name, variable.arguments, &mut context) {
+                Part::Text(text) => resolved.push_str(&text),
+                Part::Code(_) => resolved.push_str(&rest[variable.start..variable.end]),
+            }
+
+            rest = &rest[variable.end..];
+        }
+
+        resolved.push_str(rest);
+        *value = resolved;
+    }
+}
+
+/// Returns whether the value still has dynamic variables which are only known when the request is made.
+pub fn is_runtime(value: &str) -> bool {
+    find(value).is_some()
+}
+
+/// Replaces the dynamic variables which are only known when the request is made with numbers, so the text can be checked as JSON.
+/// A number is valid both as a value and inside a string, like `{{$randomInt 1 10}}` and `"{{$guid}}"`.
+/// [restore] puts the variables back in the checked text.
+pub fn placeholders(text: &str) -> (String, Vec<(String, String)>) {
+    let mut replaced = String::with_capacity(text.len());
+    let mut placeholders = Vec::new();
+    let mut next = 7_000_000_000_u64;
+    let mut rest = text;
+
+    while let Some(variable) = find(rest) {
+        while text.contains(&next.to_string()) {
+            next += 1;
+        }
+
+        let placeholder = next.to_string();
+        next += 1;
+
+        replaced.push_str(&rest[..variable.start]);
+        replaced.push_str(&placeholder);
+        placeholders.push((placeholder, rest[variable.start..variable.end].to_string()));
+        rest = &rest[variable.end..];
+    }
+
+    replaced.push_str(rest);
+
+    (replaced, placeholders)
+}
+
+/// Puts the dynamic variables replaced by [placeholders] back.
+pub fn restore(text: String, placeholders: &[(String, String)]) -> String {
+    placeholders
+        .iter()
+        .fold(text, |text, (placeholder, variable)| {
+            text.replacen(placeholder, variable, 1)
+        })
+}
+
+/// Writes a value, with the code for its dynamic variables which are only known when the request is made.
+pub fn value_tokens(value: &str) -> proc_macro2::TokenStream {
+    if !is_runtime(value) {
+        return quote! { #value };
+    }
+
+    let mut parts = Vec::new();
+    let mut rest = value;
+
+    // Only the variables which are made when the request is made are left after the value was resolved
+    let (mut files, mut env) = (Vec::new(), Vec::new());
+    let mut context = Context {
+        seeded: None,
+        runtime_env: true,
+        files: &mut files,
+        env: &mut env,
+    };
+
+    while let Some(variable) = find(rest) {
+        let text = &rest[..variable.start];
+        if !text.is_empty() {
+            parts.push(quote! { #text });
+        }
+
+        match evaluate(variable.name, variable.arguments, &mut context) {
+            Part::Text(text) => parts.push(quote! { #text }),
+            Part::Code(code) => parts.push(quote! { (#code).as_str() }),
+        }
+
+        rest = &rest[variable.end..];
+    }
+
+    if !rest.is_empty() {
+        parts.push(quote! { #rest });
+    }
+
+    quote! { [#(#parts),*].concat() }
+}
+
+/// Works out the value of a dynamic variable.
+fn evaluate(name: &str, arguments: &str, context: &mut Context) -> Part {
+    match name {
+        "guid" => random::guid(arguments, &mut context.seeded),
+        "randomInt" => random::random_int(arguments, &mut context.seeded),
+        "timestamp" => datetime::timestamp(arguments),
+        "datetime" => datetime::datetime(arguments, false),
+        "localDatetime" => datetime::datetime(arguments, true),
+        "processEnv" => env::process_env(arguments, context.runtime_env, context.env),
+        "dotenv" => env::dotenv(arguments, context.runtime_env, context.files),
+        "jwt" => jwt::token(arguments, context.files),
+        _ => abort!(
+            Span::call_site(),
+            "Unknown dynamic variable `${}`", name;
+            help = "The dynamic variables are: $guid, $randomInt, $timestamp, $datetime, $localDatetime, $processEnv, $dotenv, $jwt"
+        ),
+    }
+}
+
+/// A `{{$name arguments}}` dynamic variable in a text.
+#[derive(Debug, PartialEq, Eq)]
+struct Variable<'a> {
+    start: usize,
+    end: usize,
+    name: &'a str,
+    arguments: &'a str,
+}
+
+/// Finds the first dynamic variable in the text.
+/// The arguments can have JSON objects, so a `}}` inside braces or strings does not end the variable.
+fn find(text: &str) -> Option<Variable<'_>> {
+    let start = text.find("{{$")?;
+    let inner = &text[start + 3..];
+
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, c) in inner.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '}' if depth == 0 && inner[index..].starts_with("}}") => {
+                let variable = inner[..index].trim();
+                let (name, arguments) = variable
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((variable, ""));
+
+                return Some(Variable {
+                    start,
+                    end: start + 3 + index + 2,
+                    name,
+                    arguments: arguments.trim(),
+                });
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_variables() {
+        assert_eq!(
+            find(r#"Bearer {{$jwt HS256 key=a.key {"sub":"}}","a":{"b":1}}}} end"#),
+            Some(Variable {
+                start: 7,
+                end: 56,
+                name: "jwt",
+                arguments: r#"HS256 key=a.key {"sub":"}}","a":{"b":1}}"#,
+            })
+        );
+        assert_eq!(find("{{name}}"), None);
+        assert_eq!(find("{{$jwt"), None);
+    }
+
+    #[test]
+    fn json_placeholders() {
+        let text = r#"{ "id": "{{$guid}}", "n": {{$randomInt 1 10}}, "m": 7000000000 }"#;
+        let (replaced, placeholders) = placeholders(text);
+
+        assert_eq!(
+            replaced,
+            r#"{ "id": "7000000001", "n": 7000000002, "m": 7000000000 }"#
+        );
+        assert_eq!(restore(replaced, &placeholders), text);
+    }
+
+    #[test]
+    fn resolve_values() {
+        let mut directives = Directives::default();
+        directives.push_line("# @random-seed 7");
+        let mut uri = "/notes/{{$randomInt 1 2}}".to_string();
+        let mut headers = Vec::from([
+            ("Host".to_string(), "example.com".to_string()),
+            (
+                "X-Note".to_string(),
+                "{{$randomInt 5 6}} at {{$timestamp}}".to_string(),
+            ),
+        ]);
+        let mut body = "{{$randomInt 8 9}}".to_string();
+        resolve(
+            &mut uri,
+            &mut headers,
+            Some(&mut body),
+            &directives,
+            &mut Vec::new(),
+            &mut Vec::new(),
+        );
+
+        assert_eq!(uri, "/notes/1");
+        assert_eq!(headers[0].1, "example.com");
+        assert_eq!(headers[1].1, "5 at {{$timestamp}}");
+        assert_eq!(body, "8");
+        assert_eq!(
+            value_tokens("example.com").to_string(),
+            quote! { "example.com" }.to_string()
+        );
+
+        let Part::Code(timestamp) = datetime::timestamp("") else {
+            unreachable!("the time is only known when the request is made");
+        };
+        assert_eq!(
+            value_tokens(&headers[1].1).to_string(),
+            quote! { ["5 at ", (#timestamp).as_str()].concat() }.to_string()
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::{directives::Directives, dynamic::Part, files, token_helpers::with_tokens};
+
+/// Returns whether `# @resolve-env runtime` asks for the environment variables to be read when the request is made.
+/// By default they are read when the macro runs, like `env!`.
+pub fn at_runtime(directives: &Directives) -> bool {
+    match directives.get("resolve-env") {
+        None | Some("compile") => false,
+        Some("runtime") => true,
+        Some(other) => abort!(
+            Span::call_site(),
+            "Unknown environment resolution `{}`", other;
+            help = "Try `# @resolve-env compile` or `# @resolve-env runtime`"
+        ),
+    }
+}
+
+/// Gets the value of `{{$processEnv NAME}}`.
+/// The name is added to `env` when it is read now, so that the macro is rebuilt when the variable changes.
+pub fn process_env(arguments: &str, runtime: bool, env: &mut Vec<String>) -> Part {
+    let name = variable_name("processEnv", arguments);
+
+    if runtime {
+        return Part::Code(quote! {
+            ::std::env::var(#name).unwrap_or_else(|_| {
+                ::std::panic!("The environment variable `{}` is not set", #name)
+            })
+        });
+    }
+
+    env.push(name.to_string());
+
+    match std::env::var(name) {
+        Ok(value) => Part::Text(value),
+        Err(_) => abort!(
+            Span::call_site(),
+            "The environment variable `{}` is not set", name;
+            help = "Set it when building, or add `# @resolve-env runtime` to read it when the request is made"
+        ),
+    }
+}
+
+/// Gets the value of `{{$dotenv NAME}}` from the `.env` file next to the `Cargo.toml`.
+/// When the request is made, the `.env` file is read from the current directory instead, since the program may run on another machine.
+pub fn dotenv(arguments: &str, runtime: bool, files: &mut Vec<String>) -> Part {
+    let name = variable_name("dotenv", arguments);
+
+    if runtime {
+        let parse_dotenv = parse_dotenv_tokens();
+
+        return Part::Code(quote! {
+            {
+                #parse_dotenv
+
+                let content = ::std::fs::read_to_string(".env").unwrap_or_else(|error| {
+                    ::std::panic!("Could not read `.env` in the current directory: {}", error)
+                });
+
+                parse_dotenv(&content, #name).unwrap_or_else(|| {
+                    ::std::panic!("The variable `{}` is not in `.env`", #name)
+                })
+            }
+        });
+    }
+
+    let (full_path, content) = files::read(".env");
+    files.push(full_path.clone());
+
+    match parse_dotenv(&content, name) {
+        Some(value) => Part::Text(value),
+        None => abort!(
+            Span::call_site(),
+            "The variable `{}` is not in `{}`", name, full_path;
+            help = "Add a `{}=value` line to the file", name
+        ),
+    }
+}
+
+with_tokens! {
+    parse_dotenv_tokens,
+    /// Finds the value of a `NAME=value` line in a `.env` file.
+    /// Lines can start with `export`, and the value can be in quotes.
+    fn parse_dotenv(content: &str, name: &str) -> Option<String> {
+        content.lines().find_map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (n, value) = line.split_once('=')?;
+            let value = value.trim();
+
+            (n.trim() == name).then(|| {
+                ['"', '\'']
+                    .iter()
+                    .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+                    .unwrap_or(value)
+                    .to_string()
+            })
+        })
+    }
+}
+
+/// Gets the name of the variable, which is the only argument.
+fn variable_name<'a>(variable: &str, arguments: &'a str) -> &'a str {
+    if arguments.is_empty() || arguments.contains(char::is_whitespace) {
+        abort!(
+            Span::call_site(),
+            "`${}` takes the name of one variable", variable;
+            help = "Try `{{${} API_KEY}}`", variable
+        );
+    }
+
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dotenv_values() {
+        let content = "# The local setup\nBASE_URL=http://localhost:8000\nexport API_KEY = \"abc 123\"\nEMPTY=\n";
+
+        assert_eq!(
+            parse_dotenv(content, "BASE_URL"),
+            Some("http://localhost:8000".to_string())
+        );
+        assert_eq!(
+            parse_dotenv(content, "API_KEY"),
+            Some("abc 123".to_string())
+        );
+        assert_eq!(parse_dotenv(content, "EMPTY"), Some(String::new()));
+        assert_eq!(parse_dotenv(content, "MISSING"), None);
+    }
+}
@@ -0,0 +1,306 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+use serde_json::{Map, Value};
+
+use crate::{directives::Directives, files, variables::Variables};
+
+/// The environment variable which names the REST Client environment to use when a request does not have `# @environment`.
+pub const ENV_VAR: &str = "HTTP_MACROS_ENV";
+
+/// The environment with the variables which every environment has.
+const SHARED: &str = "$shared";
+
+/// The files with environments, from the lowest to the highest priority, and whether they are VS Code settings.
+const ENVIRONMENT_FILES: &[(&str, bool)] = &[
+    (".vscode/settings.json", true),
+    ("http-client.env.json", false),
+    ("http-client.private.env.json", false),
+];
+
+/// The key of the environments in the VS Code settings.
+const SETTINGS_KEY: &str = "rest-client.environmentVariables";
+
+/// The `{{name}}` variables of a REST Client environment, with the files and environment variable they came from.
+pub struct Environment {
+    pub variables: Variables,
+    files: Vec<String>,
+    env: Vec<String>,
+}
+
+impl Environment {
+    /// Adds the files and environment variable to be tracked when a variable was used.
+    /// Requests without variables do not need to be rebuilt when the environments change.
+    pub fn track(self, files: &mut Vec<String>, env: &mut Vec<String>) {
+        if self.variables.was_used() {
+            files.extend(self.files);
+            env.extend(self.env);
+        }
+    }
+}
+
+/// Loads the variables of the environment chosen by `# @environment name` or the `HTTP_MACROS_ENV` environment variable.
+/// Without a chosen environment only the `$shared` variables are used.
+/// The files are only read when the request can have variables, with a `{{` or a `<@` include, so other requests do not depend on them.
+/// The file variables declared with `@name = value` are added to the variables.
+/// Without any environment file or file variable, unknown variables are kept as they are, since the `{{name}}` cannot be meant for this crate.
+pub fn load(
+    directives: &Directives,
+    request: &str,
+    file_variables: Vec<(String, String)>,
+) -> Environment {
+    let allow_unknown = directives.allows("undefined-variable");
+    let mut files = Vec::new();
+    let mut env = Vec::new();
+
+    if !request.contains("{{") && !request.contains("<@") {
+        let mut variables = Variables::new(Vec::new(), allow_unknown || file_variables.is_empty());
+        variables.declare(file_variables);
+
+        return Environment {
+            variables,
+            files,
+            env,
+        };
+    }
+
+    let chosen = match directives.get("environment") {
+        Some(name) => Some(name.to_string()),
+        None => {
+            env.push(ENV_VAR.to_string());
+            std::env::var(ENV_VAR).ok().filter(|name| !name.is_empty())
+        }
+    };
+
+    let mut environments = Map::new();
+
+    for (path, is_settings) in ENVIRONMENT_FILES {
+        if !files::resolve(path).exists() {
+            continue;
+        }
+
+        let (full_path, content) = files::read(path);
+        files.push(full_path);
+
+        for (name, variables) in parse(&content, path, *is_settings) {
+            let Value::Object(variables) = variables else {
+                abort!(
+                    Span::call_site(),
+                    "The environment `{}` in `{}` is not an object", name, path;
+                    help = "Write the variables as `\"{}\": {{ \"baseUrl\": \"http://localhost\" }}`", name
+                );
+            };
+
+            let merged = environments
+                .entry(name)
+                .or_insert_with(|| Value::Object(Map::new()));
+            merged.as_object_mut().unwrap().extend(variables);
+        }
+    }
+
+    let shared = environments.get(SHARED).cloned().unwrap_or_default();
+
+    let variables = match &chosen {
+        Some(name) => {
+            let Some(variables) = environments.get(name.as_str()) else {
+                let known: Vec<_> = environments
+                    .keys()
+                    .filter(|name| *name != SHARED)
+                    .map(String::as_str)
+                    .collect();
+
+                abort!(
+                    Span::call_site(),
+                    "Unknown environment `{}`", name;
+                    help = "The environments in {} are: {}",
+                        ENVIRONMENT_FILES.iter().map(|(path, _)| format!("`{path}`")).collect::<Vec<_>>().join(", "),
+                        if known.is_empty() { "none".to_string() } else { known.join(", ") }
+                );
+            };
+
+            variables.clone()
+        }
+        None => Value::Null,
+    };
+
+    let shared = to_strings(&shared);
+    let mut merged = shared.clone();
+
+    for (name, value) in to_strings(&variables) {
+        // Like in REST Client, `{{$shared name}}` is the shared value of a variable
+        let value = shared
+            .iter()
+            .fold(value, |value, (shared_name, shared_value)| {
+                value.replace(&format!("{{{{$shared {shared_name}}}}}"), shared_value)
+            });
+
+        merged.retain(|(n, _)| *n != name);
+        merged.push((name, value));
+    }
+
+    let keep_unknown = allow_unknown || (files.is_empty() && file_variables.is_empty());
+    let mut variables = Variables::new(merged, keep_unknown);
+    variables.declare(file_variables);
+
+    Environment {
+        variables,
+        files,
+        env,
+    }
+}
+
+/// Parses the environments in a file.
+/// The VS Code settings can have comments and trailing commas, and keep the environments under a key.
+fn parse(content: &str, path: &str, is_settings: bool) -> Map<String, Value> {
+    let content = match is_settings {
+        true => strip_jsonc(content),
+        false => content.to_string(),
+    };
+
+    let value: Value = serde_json::from_str(&content).unwrap_or_else(|error| {
+        abort!(
+            Span::call_site(),
+            "Invalid JSON in `{}`: {}", path, error;
+            help = "The file has an object with an object of variables for each environment"
+        )
+    });
+
+    let environments = match is_settings {
+        true => value.get(SETTINGS_KEY).cloned().unwrap_or_default(),
+        false => value,
+    };
+
+    match environments {
+        Value::Object(environments) => environments,
+        Value::Null => Map::new(),
+        _ => abort!(
+            Span::call_site(),
+            "The environments in `{}` are not an object", path;
+            help = "Write the environments as `{{ \"local\": {{ \"baseUrl\": \"http://localhost\" }} }}`"
+        ),
+    }
+}
+
+/// Turns the values of the variables into text.
+fn to_strings(variables: &Value) -> Vec<(String, String)> {
+    let Value::Object(variables) = variables else {
+        return Vec::new();
+    };
+
+    variables
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+/// Removes the comments and trailing commas that VS Code allows in its JSON files.
+fn strip_jsonc(content: &str) -> String {
+    let mut without_comments = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_string = !in_string,
+            '\\' if in_string => {
+                without_comments.push(c);
+                without_comments.extend(chars.next());
+                continue;
+            }
+            '/' if !in_string && chars.peek() == Some(&'/') => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+                continue;
+            }
+            '/' if !in_string && chars.peek() == Some(&'*') => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == '*' && chars.next_if_eq(&'/').is_some() {
+                        break;
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        without_comments.push(c);
+    }
+
+    let mut stripped = String::with_capacity(without_comments.len());
+    let mut chars = without_comments.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_string = !in_string,
+            '\\' if in_string => {
+                stripped.push(c);
+                stripped.extend(chars.next());
+                continue;
+            }
+            ',' if !in_string => {
+                let mut ahead = chars.clone();
+                while ahead.next_if(|c| c.is_whitespace()).is_some() {}
+
+                if matches!(ahead.peek(), Some('}' | ']')) {
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        stripped.push(c);
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings() {
+        let content = r#"{
+            // The REST Client environments
+            "editor.tabSize": 4,
+            "rest-client.environmentVariables": {
+                "$shared": { "version": "v1" }, /* used everywhere */
+                "local": {
+                    "baseUrl": "http://localhost:8000",
+                    "url": "http://a//b", // with slashes
+                },
+            },
+        }"#;
+        let environments = parse(content, ".vscode/settings.json", true);
+
+        assert_eq!(
+            environments["local"],
+            serde_json::json!({ "baseUrl": "http://localhost:8000", "url": "http://a//b" })
+        );
+        assert_eq!(
+            environments["$shared"],
+            serde_json::json!({ "version": "v1" })
+        );
+    }
+
+    #[test]
+    fn env_file() {
+        let content = r#"{ "ci": { "port": 8080, "token": "abc" } }"#;
+        let environments = parse(content, "http-client.env.json", false);
+
+        assert_eq!(
+            to_strings(&environments["ci"]),
+            Vec::from([
+                ("port".to_string(), "8080".to_string()),
+                ("token".to_string(), "abc".to_string()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::variables::Variables;
+
+/// A file which is included with `< path`, or with `<@ path` to also substitute the variables in it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Include {
+    pub path: String,
+    pub substitute: bool,
+}
+
+impl Include {
+    /// Parses a `< path` or `<@ path` line.
+    pub fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix('<')?;
+        let (substitute, path) = match rest.strip_prefix('@') {
+            Some(path) => (true, path),
+            None => (false, rest),
+        };
+
+        // The space after the `<` keeps markup like `<note>` from being taken for a file
+        if !path.starts_with(char::is_whitespace) || path.trim().is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            path: path.trim().to_string(),
+            substitute,
+        })
+    }
+
+    /// Reads the file, and substitutes the variables in it when asked for.
+    /// The full path of the file is added to `files`.
+    pub fn read(&self, variables: &Variables, files: &mut Vec<String>) -> Vec<u8> {
+        let (full_path, content) = read_path(
+            resolve_include(&self.path),
+            "Paths starting with `./` are relative to the file with the macro, other paths are relative to the directory with the `Cargo.toml`",
+        );
+        files.push(full_path.clone());
+
+        if !self.substitute {
+            return content;
+        }
+
+        match String::from_utf8(content) {
+            Ok(content) => variables.substitute(&content).into_bytes(),
+            Err(_) => abort!(
+                Span::call_site(),
+                "`{}` is not valid UTF-8, so it cannot have variables", full_path;
+                help = "Use `< {}` to include the file as it is", self.path
+            ),
+        }
+    }
+}
+
+/// Resolves a path relative to the manifest directory of the crate that is using the macro.
+pub fn resolve(path: &str) -> PathBuf {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+
+    PathBuf::from(manifest_dir).join(path)
+}
+
+/// Resolves the path of an included file.
+/// Like in REST Client, paths starting with `./` or `../` are relative to the file with the macro.
+fn resolve_include(path: &str) -> PathBuf {
+    if !path.starts_with("./") && !path.starts_with("../") {
+        return resolve(path);
+    }
+
+    // The source file is relative to the directory the compiler runs in
+    match Span::call_site().local_file() {
+        Some(source) => std::env::current_dir()
+            .unwrap_or_default()
+            .join(source)
+            .parent()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|| resolve(path)),
+        None => resolve(path),
+    }
+}
+
+/// Reads a text file relative to the manifest directory.
+/// Returns the full path, which should be included in the output so that the macro is rebuilt when the file changes.
+pub fn read(path: &str) -> (String, String) {
+    let (full_path, content) = read_path(
+        resolve(path),
+        "Paths are relative to the directory with the `Cargo.toml`",
+    );
+
+    match String::from_utf8(content) {
+        Ok(content) => (full_path, content),
+        Err(_) => abort!(
+            Span::call_site(),
+            "`{}` is not valid UTF-8", full_path;
+            help = "Only text files can be used here"
+        ),
+    }
+}
+
+/// Reads the file at the full path, with the help to show when it cannot be read.
+fn read_path(full_path: PathBuf, help: &str) -> (String, Vec<u8>) {
+    match std::fs::read(&full_path) {
+        Ok(content) => (full_path.to_string_lossy().to_string(), content),
+        Err(error) => abort!(
+            Span::call_site(),
+            "Could not read `{}`: {}", full_path.display(), error;
+            help = "{}", help
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn include() {
+        assert_eq!(
+            Include::parse("< ./payload.json"),
+            Some(Include {
+                path: "./payload.json".to_string(),
+                substitute: false
+            })
+        );
+        assert_eq!(
+            Include::parse("<@  payloads/user.json "),
+            Some(Include {
+                path: "payloads/user.json".to_string(),
+                substitute: true
+            })
+        );
+        assert_eq!(Include::parse("<note>Buy milk</note>"), None);
+        assert_eq!(Include::parse("<"), None);
+        assert_eq!(Include::parse("Buy milk"), None);
+    }
+}
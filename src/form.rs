@@ -0,0 +1,150 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::{headers, url};
+
+/// Returns whether the `Content-Type` is `application/x-www-form-urlencoded`.
+pub fn is_form(headers: &[(String, String)]) -> bool {
+    headers::media_type(headers)
+        .is_some_and(|media_type| media_type == "application/x-www-form-urlencoded")
+}
+
+/// Encodes a form written as `name = value` lines.
+/// The fields can also be separated with `&`, like in the REST Client multi-line form or a form which is already encoded.
+pub fn encode(text: &str) -> String {
+    let mut fields = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim().trim_start_matches('&');
+        let readable = is_readable(line);
+
+        // A readable line is a single field, so `&` and `+` are part of its value
+        let line_fields = if readable {
+            Vec::from([line])
+        } else {
+            line.split('&').collect()
+        };
+
+        for field in line_fields
+            .into_iter()
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+        {
+            let Some((name, value)) = field.split_once('=') else {
+                malformed(field, index + 1);
+            };
+
+            let name = name.trim();
+
+            if name.is_empty() {
+                malformed(field, index + 1);
+            }
+
+            fields.push(format!(
+                "{}={}",
+                encode_text(name, readable),
+                encode_text(value.trim(), readable)
+            ));
+        }
+    }
+
+    fields.join("&")
+}
+
+/// Returns whether the line is a readable `name = value` field, with spaces around the `=`.
+fn is_readable(line: &str) -> bool {
+    line.split_once('=').is_some_and(|(name, value)| {
+        name.ends_with(char::is_whitespace) || value.starts_with(char::is_whitespace)
+    })
+}
+
+/// Percent-encodes the text while keeping the `%XX` escapes that are already in it.
+/// A `+` is also kept when the text is not readable, so that a form which is already encoded is left as it is.
+fn encode_text(text: &str, readable: bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let escape = rest.get(..3).filter(|escape| {
+            escape.starts_with('%') && escape[1..].chars().all(|c| c.is_ascii_hexdigit())
+        });
+
+        let len = match escape {
+            Some(escape) => {
+                encoded.push_str(escape);
+                3
+            }
+            None if c == '+' && !readable => {
+                encoded.push(c);
+                1
+            }
+            None => {
+                encoded.push_str(&url::encode_component(&rest[..c.len_utf8()]));
+                c.len_utf8()
+            }
+        };
+
+        rest = &rest[len..];
+    }
+
+    encoded
+}
+
+/// Aborts on a field which is not written as `name = value`.
+fn malformed(field: &str, line: usize) -> ! {
+    abort!(
+        Span::call_site(),
+        "Malformed form field `{}` at line {} of the body", field, line;
+        help = "Write each field as `name = value`"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form() {
+        let headers = Vec::from([(
+            "Content-Type".to_string(),
+            "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+        )]);
+
+        assert!(is_form(&headers));
+        assert!(!is_form(&[]));
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(
+            encode("name = John Doe\ntags = a,b\n\nempty ="),
+            "name=John%20Doe&tags=a%2Cb&empty="
+        );
+    }
+
+    #[test]
+    fn ampersands() {
+        assert_eq!(
+            encode("name=John Doe\n&tags=a,b\n&city=Zürich"),
+            "name=John%20Doe&tags=a%2Cb&city=Z%C3%BCrich"
+        );
+    }
+
+    #[test]
+    fn already_encoded() {
+        assert_eq!(
+            encode("name=John%20Doe&tags=a%2Cb&q=1+1"),
+            "name=John%20Doe&tags=a%2Cb&q=1+1"
+        );
+        assert_eq!(encode("discount = 50% off"), "discount=50%25%20off");
+    }
+
+    #[test]
+    fn readable_ampersands_and_pluses() {
+        assert_eq!(
+            encode("company = Smith & Sons\nlang = C++"),
+            "company=Smith%20%26%20Sons&lang=C%2B%2B"
+        );
+        assert_eq!(encode("a=1&b = x+y"), "a=1&b=x+y");
+    }
+}
@@ -0,0 +1,477 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::{directives::Directives, files, headers, json};
+
+/// The header REST Client uses to mark a GraphQL request, which is not sent.
+const MARKER_HEADER: &str = "X-REQUEST-TYPE";
+
+/// Returns whether the request is marked as GraphQL with `X-REQUEST-TYPE: GraphQL`.
+pub fn is_graphql(headers: &[(String, String)]) -> bool {
+    headers::get(headers, MARKER_HEADER).is_some_and(|value| value.eq_ignore_ascii_case("GraphQL"))
+}
+
+/// Returns whether the body is a GraphQL query on its own, with `Content-Type: application/graphql`.
+pub fn is_query(headers: &[(String, String)]) -> bool {
+    headers::media_type(headers).is_some_and(|media_type| media_type == "application/graphql")
+}
+
+/// Removes the marker header, since it only tells the macro how to write the body.
+pub fn remove_marker(headers: &mut Vec<(String, String)>) {
+    headers.retain(|(name, _)| !name.eq_ignore_ascii_case(MARKER_HEADER));
+}
+
+/// Wraps the query and the optional JSON variables after it into the JSON body of a GraphQL request.
+/// The query is checked first.
+pub fn wrap(text: &str, directives: &Directives, files: &mut Vec<String>) -> String {
+    let (query, variables) = split(text);
+
+    check(query, directives, files);
+
+    let mut body = serde_json::Map::new();
+    body.insert("query".to_string(), query.trim().into());
+
+    if let Some(variables) = variables {
+        // The new lines of the query are kept so that errors are at the right line of the body
+        let lines = "\n".repeat(query.matches('\n').count());
+        body.insert(
+            "variables".to_string(),
+            json::parse(&format!("{lines}{variables}")),
+        );
+    }
+
+    serde_json::Value::Object(body).to_string()
+}
+
+/// Checks the syntax of the query, and checks it against the schema of `# @graphql-schema` when it is given.
+/// The schema file is added to `files`.
+pub fn check(query: &str, directives: &Directives, files: &mut Vec<String>) {
+    if directives.allows("invalid-graphql") {
+        return;
+    }
+
+    if let Some((line, column, message)) = syntax_error(query) {
+        abort!(
+            Span::call_site(),
+            "Invalid GraphQL at line {} column {} of the body: {}", line, column, message;
+            help = "Add `# @allow invalid-graphql` if the query is invalid on purpose"
+        );
+    }
+
+    let Some(path) = directives.get("graphql-schema") else {
+        return;
+    };
+
+    let (full_path, schema) = files::read(path);
+    files.push(full_path);
+
+    let problems = schema_problems(query, &schema, path);
+
+    if !problems.is_empty() {
+        abort!(
+            Span::call_site(),
+            "The query does not match the GraphQL schema `{}`", path;
+            note = "{}", problems.join("\n");
+            help = "Add `# @allow invalid-graphql` if the query does not match on purpose"
+        );
+    }
+}
+
+/// Splits off the variables, which are the JSON object after the last blank line.
+/// A block which starts like a JSON object, with `{` and then a key or `}`, is the variables and has to be valid JSON.
+/// Other blocks, like a shorthand query after a fragment, are part of the query.
+fn split(text: &str) -> (&str, Option<&str>) {
+    let Some(blank) = text.trim_end().rfind("\n\n") else {
+        return (text, None);
+    };
+
+    let (query, variables) = text.split_at(blank);
+    let is_object = variables
+        .trim_start()
+        .strip_prefix('{')
+        .is_some_and(|rest| rest.trim_start().starts_with(['"', '}']));
+
+    if is_object && !query.trim().is_empty() {
+        (query, Some(variables))
+    } else {
+        (text, None)
+    }
+}
+
+/// Returns the line, column and message of the first syntax error in the query.
+#[cfg(feature = "graphql")]
+fn syntax_error(query: &str) -> Option<(usize, usize, String)> {
+    let error = graphql_parser::parse_query::<&str>(query)
+        .err()?
+        .to_string();
+
+    // The error looks like `query parse error: Parse error at 1:9\nUnexpected ...\nExpected ...`
+    let error = error.trim_start_matches("query parse error: ");
+    let (position, message) = error.split_once('\n').unwrap_or((error, ""));
+    let (line, column) = position
+        .trim_start_matches("Parse error at ")
+        .split_once(':')?;
+
+    Some((
+        line.parse().ok()?,
+        column.parse().ok()?,
+        message.trim().replace('\n', "; "),
+    ))
+}
+
+/// The syntax can only be checked with the feature, so nothing is checked without it.
+#[cfg(not(feature = "graphql"))]
+fn syntax_error(_query: &str) -> Option<(usize, usize, String)> {
+    None
+}
+
+/// Returns everything in the query that does not match the schema.
+#[cfg(feature = "graphql")]
+fn schema_problems(query: &str, schema: &str, path: &str) -> Vec<String> {
+    let schema = match graphql_parser::parse_schema::<&str>(schema) {
+        Ok(schema) => schema,
+        Err(error) => abort!(
+            Span::call_site(),
+            "`{}` is not a valid GraphQL schema: {}",
+            path,
+            error
+        ),
+    };
+
+    // Safe to unwrap since the syntax of the query was already checked
+    let query = graphql_parser::parse_query::<&str>(query).unwrap();
+
+    validation::check(&schema, &query)
+}
+
+#[cfg(not(feature = "graphql"))]
+fn schema_problems(_query: &str, _schema: &str, _path: &str) -> Vec<String> {
+    abort!(
+        Span::call_site(),
+        "Checking queries against a GraphQL schema needs the `graphql` feature";
+        help = "Add `features = [\"graphql\"]` to the `http-macros` dependency"
+    );
+}
+
+/// Checks that the fields and arguments in a query are in the schema.
+#[cfg(feature = "graphql")]
+mod validation {
+    use std::collections::HashMap;
+
+    use graphql_parser::{
+        query::{self, Definition, OperationDefinition, Selection, SelectionSet, TypeCondition},
+        schema::{self, Type, TypeDefinition, TypeExtension},
+        Pos,
+    };
+
+    type Fields<'a> = Vec<&'a schema::Field<'a, &'a str>>;
+
+    /// The types of a schema with the fields of the object and interface types.
+    struct Schema<'a> {
+        types: HashMap<&'a str, Fields<'a>>,
+        roots: [&'a str; 3],
+    }
+
+    impl<'a> Schema<'a> {
+        fn new(document: &'a schema::Document<'a, &'a str>) -> Self {
+            let mut types: HashMap<&str, Fields> = HashMap::new();
+            let mut roots = ["Query", "Mutation", "Subscription"];
+
+            for definition in &document.definitions {
+                let (name, fields) = match definition {
+                    schema::Definition::SchemaDefinition(schema) => {
+                        for (root, name) in roots.iter_mut().zip([
+                            schema.query,
+                            schema.mutation,
+                            schema.subscription,
+                        ]) {
+                            *root = name.unwrap_or(*root);
+                        }
+
+                        continue;
+                    }
+                    schema::Definition::TypeDefinition(TypeDefinition::Object(object)) => {
+                        (object.name, &object.fields[..])
+                    }
+                    schema::Definition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+                        (interface.name, &interface.fields[..])
+                    }
+                    schema::Definition::TypeDefinition(TypeDefinition::Union(union)) => {
+                        (union.name, &[][..])
+                    }
+                    schema::Definition::TypeExtension(TypeExtension::Object(object)) => {
+                        (object.name, &object.fields[..])
+                    }
+                    schema::Definition::TypeExtension(TypeExtension::Interface(interface)) => {
+                        (interface.name, &interface.fields[..])
+                    }
+                    _ => continue,
+                };
+
+                types.entry(name).or_default().extend(fields);
+            }
+
+            Self { types, roots }
+        }
+    }
+
+    /// Returns everything in the query that is not in the schema.
+    pub fn check<'a>(
+        schema: &'a schema::Document<'a, &'a str>,
+        query: &'a query::Document<'a, &'a str>,
+    ) -> Vec<String> {
+        let schema = Schema::new(schema);
+        let fragments: HashMap<_, _> = query
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::Fragment(fragment) => Some((fragment.name, fragment)),
+                Definition::Operation(_) => None,
+            })
+            .collect();
+
+        let mut checker = Checker {
+            schema,
+            fragments,
+            problems: Vec::new(),
+            visited: Vec::new(),
+        };
+
+        for definition in &query.definitions {
+            let Definition::Operation(operation) = definition else {
+                continue;
+            };
+
+            let (root, selection_set, position) = match operation {
+                OperationDefinition::SelectionSet(set) => (0, set, set.span.0),
+                OperationDefinition::Query(query) => (0, &query.selection_set, query.position),
+                OperationDefinition::Mutation(mutation) => {
+                    (1, &mutation.selection_set, mutation.position)
+                }
+                OperationDefinition::Subscription(subscription) => {
+                    (2, &subscription.selection_set, subscription.position)
+                }
+            };
+
+            let root = checker.schema.roots[root];
+            checker.check_type(root, position);
+            checker.check_selection_set(root, selection_set);
+        }
+
+        checker.problems
+    }
+
+    /// Walks through the selections of a query.
+    struct Checker<'a> {
+        schema: Schema<'a>,
+        fragments: HashMap<&'a str, &'a query::FragmentDefinition<'a, &'a str>>,
+        problems: Vec<String>,
+        /// The fragments that were already checked, which also stops fragments that spread themselves.
+        visited: Vec<&'a str>,
+    }
+
+    impl<'a> Checker<'a> {
+        fn check_type(&mut self, name: &str, position: Pos) -> bool {
+            let known = self.schema.types.contains_key(name);
+
+            if !known {
+                self.problems.push(format!(
+                    "The type `{name}` at line {} column {} is not in the schema",
+                    position.line, position.column
+                ));
+            }
+
+            known
+        }
+
+        fn check_selection_set(&mut self, type_name: &str, set: &'a SelectionSet<'a, &'a str>) {
+            for selection in &set.items {
+                match selection {
+                    Selection::Field(field) => {
+                        // Introspection fields are in every schema
+                        if field.name.starts_with("__") {
+                            continue;
+                        }
+
+                        let Some(definition) =
+                            self.schema.types.get(type_name).and_then(|fields| {
+                                fields
+                                    .iter()
+                                    .find(|definition| definition.name == field.name)
+                            })
+                        else {
+                            if self.schema.types.contains_key(type_name) {
+                                self.problems.push(format!(
+                                    "`{type_name}` has no field `{}` at line {} column {}",
+                                    field.name, field.position.line, field.position.column
+                                ));
+                            }
+
+                            continue;
+                        };
+
+                        for (argument, _) in &field.arguments {
+                            if !definition
+                                .arguments
+                                .iter()
+                                .any(|input| input.name == *argument)
+                            {
+                                self.problems.push(format!(
+                                    "The field `{type_name}.{}` has no argument `{argument}` at line {} column {}",
+                                    field.name, field.position.line, field.position.column
+                                ));
+                            }
+                        }
+
+                        if !field.selection_set.items.is_empty() {
+                            let inner = named_type(&definition.field_type);
+                            self.check_selection_set(inner, &field.selection_set);
+                        }
+                    }
+                    Selection::InlineFragment(fragment) => {
+                        let inner = match &fragment.type_condition {
+                            Some(TypeCondition::On(name)) => {
+                                if !self.check_type(name, fragment.position) {
+                                    continue;
+                                }
+
+                                name
+                            }
+                            None => type_name,
+                        };
+
+                        self.check_selection_set(inner, &fragment.selection_set);
+                    }
+                    Selection::FragmentSpread(spread) => {
+                        let Some(fragment) = self.fragments.get(spread.fragment_name).copied()
+                        else {
+                            self.problems.push(format!(
+                                "The fragment `{}` at line {} column {} is not defined",
+                                spread.fragment_name, spread.position.line, spread.position.column
+                            ));
+
+                            continue;
+                        };
+
+                        if self.visited.contains(&fragment.name) {
+                            continue;
+                        }
+
+                        self.visited.push(fragment.name);
+
+                        let TypeCondition::On(inner) = &fragment.type_condition;
+                        if self.check_type(inner, fragment.position) {
+                            self.check_selection_set(inner, &fragment.selection_set);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the name of the type without the lists and non-null wrappers.
+    fn named_type<'a>(field_type: &Type<'a, &'a str>) -> &'a str {
+        match field_type {
+            Type::NamedType(name) => name,
+            Type::ListType(inner) | Type::NonNullType(inner) => named_type(inner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker() {
+        let mut headers = Vec::from([
+            ("Content-Type".to_string(), "application/json".to_string()),
+            ("x-request-type".to_string(), "graphql".to_string()),
+        ]);
+        assert!(is_graphql(&headers));
+
+        remove_marker(&mut headers);
+        assert_eq!(
+            headers,
+            [("Content-Type".to_string(), "application/json".to_string())]
+        );
+        assert!(!is_graphql(&headers));
+    }
+
+    #[test]
+    fn query_and_variables() {
+        assert_eq!(
+            wrap(
+                "query ($id: ID!) {\n  user(id: $id) { name }\n}\n\n{ \"id\": 42 }",
+                &Directives::default(),
+                &mut Vec::new()
+            ),
+            r#"{"query":"query ($id: ID!) {\n  user(id: $id) { name }\n}","variables":{"id":42}}"#
+        );
+    }
+
+    #[test]
+    fn only_query() {
+        assert_eq!(
+            wrap(
+                "query {\n\n  user { name }\n}\n\nfragment Name on User { name }",
+                &Directives::default(),
+                &mut Vec::new()
+            ),
+            r#"{"query":"query {\n\n  user { name }\n}\n\nfragment Name on User { name }"}"#
+        );
+    }
+
+    #[test]
+    fn shorthand_query_after_fragment() {
+        assert_eq!(
+            wrap(
+                "fragment Name on User { name }\n\n{ user { ...Name } }",
+                &Directives::default(),
+                &mut Vec::new()
+            ),
+            r#"{"query":"fragment Name on User { name }\n\n{ user { ...Name } }"}"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "graphql")]
+    fn syntax() {
+        assert_eq!(syntax_error("query { user { name } }"), None);
+        assert_eq!(
+            syntax_error("query {\n  user { }\n}"),
+            Some((
+                2,
+                10,
+                "Unexpected `}[Punctuator]`; Expected Name".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "graphql")]
+    fn schema() {
+        let schema = "
+            type Query { user(id: ID!): User, search: [Result] }
+            type User implements Node { id: ID!, name: String, friends: [User!]! }
+            interface Node { id: ID! }
+            union Result = User
+        ";
+        let query = "
+            query {
+                user(id: 1, limit: 2) { ...UserFields, nmae, __typename }
+                search { ... on User { id }, ... on Post { id } }
+            }
+            fragment UserFields on User { name, friends { ...UserFields, id } }
+        ";
+
+        assert_eq!(
+            schema_problems(query, schema, "schema.graphql"),
+            [
+                "The field `Query.user` has no argument `limit` at line 3 column 17",
+                "`User` has no field `nmae` at line 3 column 56",
+                "The type `Post` at line 4 column 50 is not in the schema",
+            ]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::directives::Directives;
+
+/// The request headers which have a single value, so giving them more than once is a mistake.
+const SINGLE_VALUE_HEADERS: &[&str] = &[
+    "Authorization",
+    "Content-Length",
+    "Content-Type",
+    "Date",
+    "From",
+    "Host",
+    "If-Modified-Since",
+    "If-Range",
+    "If-Unmodified-Since",
+    "Max-Forwards",
+    "Proxy-Authorization",
+    "Referer",
+    "User-Agent",
+];
+
+/// Adds a header line to the headers, where a repeated header adds another value.
+/// The `+Name:`, `=Name:` and `-Name` prefixes only change the headers of a template, so they are stopped anywhere else.
+pub fn push(headers: &mut Vec<(String, String)>, name: &str, value: String) {
+    if let Some(prefix) = name.chars().next().filter(|c| matches!(c, '+' | '=' | '-')) {
+        abort!(
+            Span::call_site(),
+            "Unexpected `{}` before the `{}` header", prefix, &name[1..];
+            help = "`+Name: value`, `=Name: value` and `-Name` only change the headers of a template in `request!(extends NAME, \"...\")`. Repeat a header line to add another value"
+        );
+    }
+
+    headers.push((name.to_string(), value));
+}
+
+/// Stops on a trailer that cannot be sent.
+/// Trailers are made with `from_static` in the generated code, which would only panic when the request is made.
+pub fn check_trailer(name: &str, value: &str) {
+    let is_token = |byte: u8| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte);
+
+    if name.is_empty() || !name.bytes().all(is_token) {
+        abort!(
+            Span::call_site(),
+            "Invalid trailer name `{}`", name;
+            help = "A trailer name only has letters, digits and the characters ``!#$%&'*+-.^_`|~``"
+        );
+    }
+
+    if !value
+        .bytes()
+        .all(|byte| byte == b'\t' || (b' '..=b'~').contains(&byte))
+    {
+        abort!(
+            Span::call_site(),
+            "Invalid value `{}` for the `{}` trailer", value, name;
+            help = "A trailer value only has visible ASCII characters, spaces and tabs"
+        );
+    }
+}
+
+/// Stops when a header with a single value is given more than once, unless `# @allow duplicate-header` is given.
+pub fn check_duplicates(directives: &Directives, headers: &[(String, String)]) {
+    if directives.allows("duplicate-header") {
+        return;
+    }
+
+    for (index, (name, _)) in headers.iter().enumerate() {
+        let single = SINGLE_VALUE_HEADERS
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case(name));
+
+        if single
+            && headers[..index]
+                .iter()
+                .any(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            abort!(
+                Span::call_site(),
+                "The `{}` header is given more than once", name;
+                help = "Use `={}: value` to replace the earlier value, or add `# @allow duplicate-header` to send both", name
+            );
+        }
+    }
+}
+
+/// Returns the value of the first header with the name (ignoring case).
+pub fn get<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Returns the media type of the `Content-Type` header in lower case, without any parameters.
+pub fn media_type(headers: &[(String, String)]) -> Option<String> {
+    get(headers, "Content-Type")
+        .and_then(|value| value.split(';').next())
+        .map(|media_type| media_type.trim().to_lowercase())
+}
+
+/// Returns the value of a parameter of the `Content-Type` header (ignoring case), without any quotes.
+pub fn content_type_parameter<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    get(headers, "Content-Type")?
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(n, _)| n.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().trim_matches('"'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_type() {
+        let headers = Vec::from([(
+            "content-type".to_string(),
+            "Application/JSON; charset=utf-8".to_string(),
+        )]);
+
+        assert_eq!(
+            get(&headers, "Content-Type"),
+            Some("Application/JSON; charset=utf-8")
+        );
+        assert_eq!(media_type(&headers), Some("application/json".to_string()));
+        assert_eq!(media_type(&[]), None);
+        assert_eq!(content_type_parameter(&headers, "Charset"), Some("utf-8"));
+        assert_eq!(content_type_parameter(&headers, "boundary"), None);
+    }
+}
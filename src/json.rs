@@ -0,0 +1,171 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::{directives::Directives, headers};
+
+/// How a JSON body is written in the request.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// As it is written in the macro.
+    Verbatim,
+    /// Without any whitespace between the tokens.
+    Minify,
+    /// Minified with the object keys sorted.
+    Canonical,
+}
+
+impl Format {
+    /// Gets the format from the `# @json` directive.
+    pub fn new(directives: &Directives) -> Self {
+        match directives.get("json") {
+            None => Self::Verbatim,
+            Some("minify") => Self::Minify,
+            Some("canonical") => Self::Canonical,
+            Some(other) => abort!(
+                Span::call_site(),
+                "Unknown JSON format `{}`", other;
+                help = "Try `# @json minify` or `# @json canonical`"
+            ),
+        }
+    }
+}
+
+/// Returns whether the `Content-Type` is JSON, ie `application/json` or a `+json` type.
+pub fn is_json(headers: &[(String, String)]) -> bool {
+    headers::media_type(headers)
+        .is_some_and(|media_type| media_type == "application/json" || media_type.ends_with("+json"))
+}
+
+/// Checks that the text is well-formed JSON and writes it in the format.
+pub fn process(text: &str, format: Format, directives: &Directives) -> String {
+    // A JSON request without a body is fine
+    if text.trim().is_empty() {
+        return text.to_string();
+    }
+
+    let value = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(value) => value,
+        Err(_) if directives.allows("invalid-json") => return text.to_string(),
+        Err(error) => invalid(
+            text,
+            &error,
+            "Add `# @allow invalid-json` if the body is invalid on purpose",
+        ),
+    };
+
+    match format {
+        Format::Verbatim => text.to_string(),
+        Format::Minify => minify(text),
+        Format::Canonical => sort_keys(value).to_string(),
+    }
+}
+
+/// Parses a body which has to be converted from JSON, so it cannot be invalid on purpose.
+pub fn parse(text: &str) -> serde_json::Value {
+    serde_json::from_str(text).unwrap_or_else(|error| {
+        invalid(
+            text,
+            &error,
+            "The body is converted from JSON, so it has to be valid",
+        )
+    })
+}
+
+/// Aborts with the place of the error in the invalid JSON.
+fn invalid(text: &str, error: &serde_json::Error, help: &str) -> ! {
+    let line = text
+        .lines()
+        .nth(error.line().saturating_sub(1))
+        .unwrap_or_default();
+
+    // The message of the error already ends with the location, which is given separately here
+    let message = error.to_string();
+    let message = message.split(" at line ").next().unwrap_or_default();
+
+    abort!(
+        Span::call_site(),
+        "Invalid JSON at line {} column {} of the body: {}", error.line(), error.column(), message;
+        help = "The line is `{}`. {}", line, help
+    );
+}
+
+/// Sorts the keys of all the objects in the value.
+fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().map(|(k, v)| (k, sort_keys(v))).collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            serde_json::Value::Object(entries.into_iter().collect())
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(sort_keys).collect())
+        }
+        other => other,
+    }
+}
+
+/// Removes all the whitespace outside of strings from valid JSON.
+fn minify(text: &str) -> String {
+    let mut minified = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in text.chars() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c.is_whitespace() {
+            continue;
+        } else {
+            in_string = c == '"';
+        }
+
+        minified.push(c);
+    }
+
+    minified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content_type(value: &str) -> Vec<(String, String)> {
+        Vec::from([("Content-Type".to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn json() {
+        assert!(is_json(&content_type("application/json")));
+        assert!(is_json(&content_type(
+            "application/problem+json; charset=utf-8"
+        )));
+        assert!(!is_json(&content_type("text/plain")));
+        assert!(!is_json(&[]));
+    }
+
+    #[test]
+    fn formats() {
+        let text = "{ \"name\": \"John \\\" Doe\",\n  \"age\": 42 }\n";
+        let directives = Directives::default();
+
+        assert_eq!(process(text, Format::Verbatim, &directives), text);
+        assert_eq!(
+            process(text, Format::Minify, &directives),
+            "{\"name\":\"John \\\" Doe\",\"age\":42}"
+        );
+        assert_eq!(
+            process(text, Format::Canonical, &directives),
+            "{\"age\":42,\"name\":\"John \\\" Doe\"}"
+        );
+    }
+
+    #[test]
+    fn invalid_allowed() {
+        let mut directives = Directives::default();
+        directives.push_line("# @allow invalid-json");
+
+        assert_eq!(process("{ oops", Format::Minify, &directives), "{ oops");
+    }
+}
@@ -0,0 +1,245 @@
+use base64::Engine;
+use proc_macro::Span;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use serde_json::{Map, Value};
+
+use crate::{dynamic::Part, files::Include, variables::Variables};
+
+const HELP: &str = r#"Try `{{$jwt HS256 key=tests/keys/hmac.key {"sub":"alice","exp":"+1h"}}}`"#;
+
+/// The HMAC algorithms tokens can be signed with.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Algorithm {
+    Hs256,
+    Hs384,
+    Hs512,
+}
+
+impl Algorithm {
+    fn new(name: &str) -> Self {
+        match name {
+            "HS256" => Self::Hs256,
+            "HS384" => Self::Hs384,
+            "HS512" => Self::Hs512,
+            other => abort!(
+                Span::call_site(),
+                "Unsupported JWT algorithm `{}`", other;
+                help = "The supported algorithms are HS256, HS384 and HS512"
+            ),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hs256 => "HS256",
+            Self::Hs384 => "HS384",
+            Self::Hs512 => "HS512",
+        }
+    }
+
+    /// The name of the hash in the `sha2` crate.
+    fn hash(self) -> &'static str {
+        match self {
+            Self::Hs256 => "Sha256",
+            Self::Hs384 => "Sha384",
+            Self::Hs512 => "Sha512",
+        }
+    }
+}
+
+/// Makes the signed token of `{{$jwt ALGORITHM key=path claims}}`.
+/// Claims with relative times, like `"exp": "+1h"`, are only known when the request is made, so those tokens are signed then.
+pub fn token(arguments: &str, files: &mut Vec<String>) -> Part {
+    let Some((algorithm, rest)) = arguments.split_once(char::is_whitespace) else {
+        abort!(
+            Span::call_site(),
+            "Missing the key and claims of `$jwt`";
+            help = "{}", HELP
+        );
+    };
+    let algorithm = Algorithm::new(algorithm);
+
+    let Some((key, claims)) = rest
+        .trim_start()
+        .strip_prefix("key=")
+        .and_then(|rest| rest.split_once(char::is_whitespace))
+    else {
+        abort!(
+            Span::call_site(),
+            "Missing the `key=` file or the claims of `$jwt`";
+            help = "{}", HELP
+        );
+    };
+    let key = read_key(key, files);
+
+    let claims: Map<String, Value> = serde_json::from_str(claims).unwrap_or_else(|error| {
+        abort!(
+            Span::call_site(),
+            "Invalid JWT claims: {}", error;
+            help = "The claims are a JSON object, like `{\"sub\":\"alice\"}`"
+        )
+    });
+
+    let header = encode(format!(r#"{{"alg":"{}","typ":"JWT"}}"#, algorithm.name()));
+
+    if !claims.values().any(|value| offset(value).is_some()) {
+        let message = format!("{header}.{}", encode(Value::Object(claims).to_string()));
+        let signature = encode(sign(algorithm, &key, &message));
+
+        return Part::Text(format!("{message}.{signature}"));
+    }
+
+    Part::Code(runtime_token(algorithm, &key, &header, &claims))
+}
+
+/// Writes the code which signs the token when the request is made, with the relative times added to the current time.
+fn runtime_token(
+    algorithm: Algorithm,
+    key: &[u8],
+    header: &str,
+    claims: &Map<String, Value>,
+) -> proc_macro2::TokenStream {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+
+    for (index, (name, value)) in claims.iter().enumerate() {
+        text.push(if index == 0 { '{' } else { ',' });
+        text.push_str(&format!("{}:", Value::String(name.clone())));
+
+        match offset(value) {
+            Some(offset) => {
+                pieces.push(quote! { #text });
+                pieces.push(quote! { (now + #offset).to_string().as_str() });
+                text.clear();
+            }
+            None => text.push_str(&value.to_string()),
+        }
+    }
+
+    text.push('}');
+    pieces.push(quote! { #text });
+
+    let key = proc_macro2::Literal::byte_string(key);
+    let hash = format_ident!("{}", algorithm.hash());
+
+    quote! {
+        {
+            use ::base64::Engine as _;
+            use ::hmac::Mac as _;
+
+            let engine = ::base64::engine::general_purpose::URL_SAFE_NO_PAD;
+            let now = ::std::time::SystemTime::now()
+                .duration_since(::std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            let claims = [#(#pieces),*].concat();
+            let message = ::std::format!("{}.{}", #header, engine.encode(claims));
+            let mut mac = ::hmac::Hmac::<::sha2::#hash>::new_from_slice(#key).unwrap();
+            mac.update(message.as_bytes());
+
+            ::std::format!("{}.{}", message, engine.encode(mac.finalize().into_bytes()))
+        }
+    }
+}
+
+/// Reads the key file like an included file.
+/// A trailing newline is not part of the key, since most editors add one.
+fn read_key(path: &str, files: &mut Vec<String>) -> Vec<u8> {
+    let include = Include {
+        path: path.to_string(),
+        substitute: false,
+    };
+    let mut key = include.read(&Variables::default(), files);
+
+    while key
+        .last()
+        .is_some_and(|byte| *byte == b'\n' || *byte == b'\r')
+    {
+        key.pop();
+    }
+
+    key
+}
+
+/// Gets the seconds of a relative time like `"+1h"`, `"-30m"`, `"+90s"` or `"+7d"`.
+fn offset(value: &Value) -> Option<i64> {
+    let value = value.as_str()?;
+    let (sign, rest) = match value.strip_prefix('+') {
+        Some(rest) => (1, rest),
+        None => (-1, value.strip_prefix('-')?),
+    };
+    let unit = match rest.chars().last()? {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+    let amount: i64 = rest[..rest.len() - 1].parse().ok()?;
+
+    Some(sign * amount * unit)
+}
+
+/// Encodes the part of a token to base64url without padding.
+fn encode(data: impl AsRef<[u8]>) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data)
+}
+
+#[cfg(feature = "jwt")]
+fn sign(algorithm: Algorithm, key: &[u8], message: &str) -> Vec<u8> {
+    use hmac::{Hmac, Mac};
+
+    fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &str) -> Vec<u8> {
+        // HMAC takes keys of any length
+        let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+        mac.update(message.as_bytes());
+
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    match algorithm {
+        Algorithm::Hs256 => mac::<Hmac<sha2::Sha256>>(key, message),
+        Algorithm::Hs384 => mac::<Hmac<sha2::Sha384>>(key, message),
+        Algorithm::Hs512 => mac::<Hmac<sha2::Sha512>>(key, message),
+    }
+}
+
+#[cfg(not(feature = "jwt"))]
+fn sign(_algorithm: Algorithm, _key: &[u8], _message: &str) -> Vec<u8> {
+    abort!(
+        Span::call_site(),
+        "Signing a JWT when the macro runs needs the `jwt` feature";
+        help = "Add `features = [\"jwt\"]` to the `http-macros` dependency"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets() {
+        assert_eq!(offset(&Value::from("+1h")), Some(3600));
+        assert_eq!(offset(&Value::from("-30m")), Some(-1800));
+        assert_eq!(offset(&Value::from("+7d")), Some(604800));
+        assert_eq!(offset(&Value::from("alice")), None);
+        assert_eq!(offset(&Value::from("+1y")), None);
+        assert_eq!(offset(&Value::from(3600)), None);
+    }
+
+    #[test]
+    #[cfg(feature = "jwt")]
+    fn signature() {
+        // The example token of RFC 7515, appendix A.1
+        let key = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode("AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow")
+            .unwrap();
+        let message = "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9.eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ";
+
+        assert_eq!(
+            encode(sign(Algorithm::Hs256, &key, message)),
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"
+        );
+    }
+}
@@ -3,10 +3,38 @@
 use proc_macro::{Span, TokenStream};
 use proc_macro_error::{abort, proc_macro_error};
 
+mod auth;
+mod aws;
+mod body;
+mod body_encoding;
+mod charset;
+mod compression;
+mod content_length;
+mod datetime;
+mod directives;
+mod dynamic;
+mod env;
+mod environment;
+mod files;
+mod form;
+mod graphql;
+mod headers;
+mod json;
+mod jwt;
+mod multipart;
+mod openapi;
+mod openapi_requests;
 mod parser;
+mod random;
+mod raw_request;
 mod request;
 mod request_builder;
+mod schema;
+mod serialization;
+mod template;
 mod token_helpers;
+mod url;
+mod variables;
 
 /// Makes it easy to create a [http::request::Builder] from a request string that follows the RFC 7230 spec.
 /// This allows you to manually set a body that is not supported by [request!].
@@ -42,6 +70,10 @@ mod token_helpers;
 #[proc_macro_error]
 #[proc_macro]
 pub fn request_builder(input: TokenStream) -> TokenStream {
+    if let Some(expansion) = template::extends(&input, "request_builder") {
+        return expansion.into();
+    }
+
     let input = get_request(input);
 
     let builder = request_builder::RequestBuilder::new(&input);
@@ -88,9 +120,426 @@ pub fn request_builder(input: TokenStream) -> TokenStream {
 /// assert_eq!(request.headers().get("Content-Type").unwrap(), "application/json");
 /// assert_eq!(request.body(), &r#"{ "name": "John Doe" }"#);
 /// ```
+///
+/// # Content-Length
+/// An explicit `Content-Length` header is checked against the number of bytes in the body and gives a compile error when they do not match.
+/// Add a `# @content-length` comment before the request line to have the header set automatically.
+/// Or add `# @allow content-length-mismatch` to send a wrong length on purpose.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"# @content-length
+///       POST /hello
+///
+///       Hello"#);
+///
+/// assert_eq!(request.headers().get("Content-Length").unwrap(), "5");
+/// ```
+///
+/// # Repeated headers
+/// A header which is given on more than one line gets all the values, like `Accept` or `Cookie` can.
+/// The `+Name`, `=Name` and `-Name` prefixes only change the headers of a template, see [request_template!].
+/// A header which only has one value, like `Host`, `Content-Length` or `Authorization`, gives a compile error when it is given more than once.
+/// Add `# @allow duplicate-header` to send it more than once on purpose.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "GET /hello
+///     Accept: text/plain
+///     Accept: text/html
+///     Host: example.com");
+///
+/// assert_eq!(request.headers().get_all("Accept").iter().count(), 2);
+/// ```
+///
+/// # Authorization
+/// Like in REST Client, an `Authorization: Basic user:password` or `Authorization: Basic user password` header has its credentials encoded to base64.
+/// Credentials which are already encoded are sent as they are.
+/// Credentials with values that are only known when the request is made are encoded then, which needs the `base64` crate to be a dependency too.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "GET /reminders
+///     Authorization: Basic alice:secret");
+///
+/// assert_eq!(request.headers().get("Authorization").unwrap(), "Basic YWxpY2U6c2VjcmV0");
+/// ```
+///
+/// # Dynamic variables
+/// The URI, headers and a text body can use the dynamic variables of REST Client, which are made each time the request is made:
+/// - `{{$guid}}` is a random version 4 UUID.
+/// - `{{$randomInt min max}}` is a random whole number from `min` up to, but not including, `max`.
+/// - `{{$timestamp}}` is the seconds since 1970, and `{{$timestamp -3 h}}` is three hours earlier.
+/// - `{{$datetime iso8601}}`, `{{$datetime rfc1123}}` or `{{$datetime "DD-MM-YYYY"}}` is the current time in UTC, which can also have an offset.
+/// - `{{$localDatetime iso8601}}` is the same in the local time zone.
+///
+/// The offset units are `y`, `M`, `w`, `d`, `h`, `m`, `s` and `ms`.
+/// `{{$datetime}}` and `{{$localDatetime}}`, and `{{$timestamp}}` with a `y` or `M` offset, need the `chrono` crate to be a dependency too.
+/// A `# @random-seed 42` directive makes the random values when the macro runs instead, so that they are the same on every test run.
+/// A body with values that are made with the request is sent as it is written, so it cannot be encoded, chunked or have a `Content-Length`, and it does not work with `raw_request!`.
+/// In a JSON body a variable can also be a value on its own, like `"priority": {{$randomInt 1 10}}`.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "PUT /notes/{{$guid}}
+///     If-Unmodified-Since: {{$datetime rfc1123 -1 d}}");
+///
+/// assert_eq!(request.uri().path().len(), "/notes/".len() + 36);
+/// assert!(request.headers()["If-Unmodified-Since"].to_str().unwrap().ends_with(" GMT"));
+/// ```
+///
+/// # Environment variables
+/// Like in REST Client, `{{$processEnv NAME}}` is the value of an environment variable, and `{{$dotenv NAME}}` is a value from the `.env` file next to the `Cargo.toml`.
+/// They are read when the macro runs, like `env!`, and the macro is rebuilt when they change.
+/// Add `# @resolve-env runtime` to read them each time the request is made instead, with the `.env` file in the current directory.
+/// A variable which is missing gives a compile error, or a panic when it is read at runtime.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "GET {{$dotenv BASE_URL}}/notes
+///     X-Api-Key: {{$processEnv API_KEY}}");
+/// ```
+///
+/// # Environments
+/// `{{name}}` variables are read from the REST Client environments in `.vscode/settings.json`, `http-client.env.json` and `http-client.private.env.json` next to the `Cargo.toml`.
+/// The variables in `$shared` are always available, and a value can use `{{$shared name}}` to refer to one of them.
+/// The environment is chosen with `# @environment name`, or else with the `HTTP_MACROS_ENV` environment variable, which can be set in the `[env]` table of `.cargo/config.toml`.
+/// The files are only read for requests with a `{{` or a `<@` include.
+/// When there is an environment file or a file variable, an unknown variable gives a compile error, and `# @allow undefined-variable` sends it as it is, like the `{{name}}` of a Mustache template in the body.
+/// Without either, every `{{name}}` is sent as it is.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "# @environment local
+///     GET {{baseUrl}}/notes
+///     Authorization: Bearer {{token}}");
+/// ```
+///
+/// # File variables
+/// Like in a REST Client file, `@name = value` lines before the request line declare variables for `{{name}}`.
+/// A file variable can use other variables in its value, and takes precedence over a variable from the environment with the same name.
+/// A file variable which refers back to itself gives a compile error.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "@host = localhost:8080
+///     @url = http://{{host}}/api
+///     GET {{url}}/notes");
+///
+/// assert_eq!(request.uri(), "http://localhost:8080/api/notes");
+/// ```
+///
+/// # JWTs
+/// A `{{$jwt ALGORITHM key=path/to/key claims}}` variable in a header is replaced by a JWT with the JSON claims, signed with the key file.
+/// The algorithm is `HS256`, `HS384` or `HS512`, the key file is found like an included file, and a trailing newline is not part of the key.
+/// Tokens are signed when the macro runs, which needs the `jwt` feature.
+/// Claims with relative times, like `"exp": "+1h"` or `"nbf": "-5m"`, are only known when the request is made, so those tokens are signed then.
+/// This needs the `base64`, `hmac` and `sha2` crates to be dependencies too, and does not work with `raw_request!`.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"GET /reminders
+///       Authorization: Bearer {{$jwt HS256 key=tests/keys/hmac.key {"sub":"alice","exp":"+1h"}}}"#);
+/// ```
+///
+/// # AWS Signature Version 4
+/// Like in REST Client, a request with an `Authorization: AWS <access key> <secret key>` header is signed with AWS Signature Version 4, which needs the `aws` feature.
+/// The header can end with `region:<region>`, `service:<service>` and `token:<session token>`, and the region and service are otherwise taken from an `amazonaws.com` host.
+/// All the headers are signed, and the `Authorization` and `X-Amz-Date` headers are set.
+/// A `# @aws-date 20150830T123600Z` directive signs the request for that time when the macro runs, which keeps the signature the same between test runs.
+/// Without it the request is signed for the current time when it is made, so the `hmac` and `sha2` crates need to be dependencies too, and `raw_request!` cannot be used.
+/// `request_builder!` gives a compile error for these requests, since the signature covers the body which is only set later.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "# @aws-date 20150830T123600Z
+///     GET https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08
+///     Authorization: AWS AKIDEXAMPLE wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY");
+///
+/// assert_eq!(request.headers().get("X-Amz-Date").unwrap(), "20150830T123600Z");
+/// ```
+///
+/// # Chunked bodies
+/// When the request has a `Transfer-Encoding: chunked` header, then the body becomes an [http_body::Body](https://docs.rs/http-body/latest/http_body/trait.Body.html) which yields each chunk as a separate frame.
+/// So the `http-body` crate needs to be a dependency too for these requests.
+///
+/// The body can be written as plain text, which is sent as a single chunk or split into chunks with `# @chunk-size 16`.
+/// Or it can be written as explicit chunks which each start with a `--- chunk ---` line.
+/// Trailer fields can follow after a `--- trailers ---` line.
+/// ```rust
+/// use http_body::Body;
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "POST /hello
+///     Transfer-Encoding: chunked
+///
+///     --- chunk ---
+///     Hello
+///     --- chunk ---
+///     World
+///     --- trailers ---
+///     Expires: never
+/// ");
+///
+/// assert_eq!(request.headers().get("Transfer-Encoding").unwrap(), "chunked");
+/// assert!(!request.body().is_end_stream());
+/// ```
+///
+/// # Trailers
+/// Any request can have trailer fields in a `--- trailers ---` section after the body.
+/// The body then becomes an [http_body::Body](https://docs.rs/http-body/latest/http_body/trait.Body.html) which yields the data and then the trailers as a [http::HeaderMap].
+/// ```rust
+/// use http_body::Body;
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "POST /hello
+///     Content-Type: application/grpc
+///
+///     Hello
+///     --- trailers ---
+///     Grpc-Status: 0
+/// ");
+///
+/// assert_eq!(request.body().size_hint().exact(), None);
+/// ```
+///
+/// # JSON
+/// When the `Content-Type` is `application/json` or ends with `+json`, then the body is checked to be well-formed JSON.
+/// Add `# @allow invalid-json` to send malformed JSON on purpose.
+/// The body can also be minified with `# @json minify`, or minified with sorted keys with `# @json canonical`.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"# @json canonical
+///       POST /hello
+///       Content-Type: application/json
+///
+///       {
+///           "name": "John Doe",
+///           "age": 42
+///       }"#);
+///
+/// assert_eq!(request.body(), r#"{"age":42,"name":"John Doe"}"#);
+/// ```
+///
+/// # GraphQL
+/// A request with the `X-REQUEST-TYPE: GraphQL` header of REST Client has a GraphQL query as its body, optionally followed by a blank line and a JSON object of variables.
+/// These are wrapped into the `{"query": ..., "variables": ...}` JSON body and the marker header is not sent.
+/// A last block which starts like a JSON object, with `{` and then a key or `}`, is the variables and has to be valid JSON, while a shorthand `{ user { id } }` query after a fragment is part of the query.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"POST /graphql
+///       Content-Type: application/json
+///       X-REQUEST-TYPE: GraphQL
+///
+///       query ($id: ID!) { user(id: $id) { name } }
+///
+///       { "id": 42 }"#);
+///
+/// assert!(request.headers().get("X-REQUEST-TYPE").is_none());
+/// assert_eq!(
+///     request.body(),
+///     r#"{"query":"query ($id: ID!) { user(id: $id) { name } }","variables":{"id":42}}"#
+/// );
+/// ```
+///
+/// With the `graphql` feature, the query of a GraphQL request, or a body with `Content-Type: application/graphql`, is parsed when the macro runs so that syntax errors fail the build.
+/// It is also checked against a schema with `# @graphql-schema path/to/schema.graphql`, so fields and arguments that are not in the schema fail the build too.
+/// Add `# @allow invalid-graphql` to send an invalid query on purpose.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "# @graphql-schema schema.graphql
+///     POST /graphql
+///     Content-Type: application/graphql
+///
+///     { user(id: 42) { name } }");
+/// ```
+///
+/// # JSON Schema
+/// A JSON body can also be validated against a JSON Schema with `# @schema path/to/schema.json`.
+/// The path is relative to the directory with the `Cargo.toml` and the body has to match the schema for the macro to compile.
+/// This needs the `json-schema` feature.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"# @schema schemas/create_user.json
+///       POST /users
+///       Content-Type: application/json
+///
+///       { "name": "John Doe" }"#);
+/// ```
+///
+/// # OpenAPI
+/// A request can be checked against an OpenAPI document with `# @openapi path/to/openapi.yaml`,
+/// or for every request by setting the `HTTP_MACROS_OPENAPI` environment variable to the path of the document.
+/// The path is relative to the directory with the `Cargo.toml`.
+/// The method, path, query parameters, headers and JSON body then have to match an operation in the document for the macro to compile.
+/// Add `# @allow openapi-mismatch` to a request that does not match on purpose.
+/// This needs the `openapi` feature.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"# @openapi openapi.yaml
+///       POST /users
+///       Content-Type: application/json
+///
+///       { "name": "John Doe" }"#);
+/// ```
+///
+/// # MessagePack and CBOR
+/// When the `Content-Type` is `application/msgpack` or `application/cbor`, then the body is written as JSON and converted to that format when the macro runs.
+/// The body is then a `Vec<u8>` of the converted bytes.
+/// This needs the `msgpack` or `cbor` feature.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"POST /hello
+///       Content-Type: application/msgpack
+///
+///       { "name": "John Doe" }"#);
+///
+/// assert_eq!(request.body()[0], 0x81);
+/// ```
+///
+/// # Binary bodies
+/// A binary body can be written as base64 with `# @body-encoding base64`, or as hex with `# @body-encoding hex`.
+/// The marker goes before the request line, or on the first line of the body.
+/// The data can be wrapped over many lines and is decoded when the macro runs, so the body is a `Vec<u8>` of the decoded bytes.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "POST /messages
+///     Content-Type: application/x-protobuf
+///
+///     ## @body-encoding hex
+///     08 96 01");
+///
+/// assert_eq!(request.body(), &[0x08, 0x96, 0x01]);
+/// ```
+///
+/// # Files
+/// A body which is only a `< path/to/file` line is read from the file, like in REST Client.
+/// Paths starting with `./` or `../` are relative to the file with the macro, and other paths are relative to the directory with the `Cargo.toml`.
+/// With `<@ path/to/file` the `{{name}}` variables in the file are also replaced.
+/// The macro is rebuilt when the file changes, and a file which is not UTF-8 is sent as a `Vec<u8>` of its bytes.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "POST /users
+///     Content-Type: application/json
+///
+///     < ./payloads/user.json");
+/// ```
+///
+/// # Forms
+/// When the `Content-Type` is `application/x-www-form-urlencoded`, then the body can be written as readable `name = value` lines.
+/// With spaces around the `=`, a line is a single field and everything in it is percent-encoded, so `Smith & Sons` and `C++` are sent as they are written.
+/// Fields without those spaces, like `name=John&city=Paris` or the multi-line forms of REST Client with lines starting with `&`, are split on `&` and keep their `+` for a form which is already encoded.
+/// Escapes like `%26` are always kept.
+/// A field without a name or `=` gives a compile error.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "POST /users
+///     Content-Type: application/x-www-form-urlencoded
+///
+///     name = John Doe
+///     company = Smith & Sons
+///     lang = C++");
+///
+/// assert_eq!(request.body(), "name=John%20Doe&company=Smith%20%26%20Sons&lang=C%2B%2B");
+/// ```
+///
+/// # Multipart
+/// When the `Content-Type` is `multipart/form-data`, then each part starts with a `--- part name="field" ---` line.
+/// The rest of the line is added to the `Content-Disposition` of the part, so a file part can be written as `--- part name="file"; filename="file.txt" ---`.
+/// The lines before the first blank line of a part are its headers, so start the part with a blank line when its content has blank lines.
+/// The content can also be `< path/to/file` to include a file, or `<@ path/to/file` to also replace the `{{name}}` variables in it.
+/// Like for a whole body, paths starting with `./` or `../` are relative to the file with the macro, and other paths are relative to the directory with the `Cargo.toml`.
+/// A boundary which does not appear in any part is added to the `Content-Type` and the body is a `Vec<u8>` of the encoded parts.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"POST /upload
+///       Content-Type: multipart/form-data
+///
+///       --- part name="title" ---
+///       Shopping
+///
+///       --- part name="note"; filename="note.txt" ---
+///       Content-Type: text/plain
+///
+///       Buy milk"#);
+///
+/// assert_eq!(
+///     request.headers()["Content-Type"],
+///     "multipart/form-data; boundary=http-macros-boundary"
+/// );
+/// assert!(request.body().starts_with(b"--http-macros-boundary\r\n"));
+/// ```
+///
+/// # Charset
+/// The body is written in UTF-8, but is encoded in the `charset` of the `Content-Type` header when it has one.
+/// The body is then a `Vec<u8>` of the encoded bytes.
+/// Characters which cannot be represented in the charset give a compile error.
+/// `ISO-8859-1` is real Latin-1, so `€` is an error there, while `windows-1252` can encode it.
+/// ```rust
+/// use http_macros::request;
+///
+/// let request = request!(
+///    "POST /hello
+///     Content-Type: text/plain; charset=ISO-8859-1
+///
+///     café");
+///
+/// assert_eq!(request.body(), b"caf\xe9");
+/// ```
+///
+/// # Content-Encoding
+/// When the request has a `Content-Encoding` header, then the body is written as readable text and compressed when the macro runs.
+/// The body is then a `Vec<u8>` of the compressed bytes.
+/// Each coding needs its feature to be turned on: `gzip`, `deflate`, `br` or `zstd`.
+/// ```rust,ignore
+/// use http_macros::request;
+///
+/// let request = request!(
+///    r#"POST /hello
+///       Content-Encoding: gzip
+///
+///       { "name": "John Doe" }"#);
+///
+/// assert_eq!(&request.body()[..2], &[0x1f, 0x8b]);
+/// ```
 #[proc_macro_error]
 #[proc_macro]
 pub fn request(input: TokenStream) -> TokenStream {
+    if let Some(expansion) = template::extends(&input, "request") {
+        return expansion.into();
+    }
+
     let input = get_request(input);
 
     let request = request::Request::new(&input);
@@ -102,8 +551,134 @@ pub fn request(input: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Creates the raw bytes of a request as it is sent over the wire for HTTP/1.x.
+/// This takes the same input as [request!] and is useful for testing code that parses requests itself.
+/// The version can only be HTTP/1.0 or HTTP/1.1, which is the default.
+///
+/// # Example
+/// ```rust
+/// use http_macros::raw_request;
+///
+/// let raw = raw_request!(
+///    "POST /hello
+///     Host: example.com
+///     Transfer-Encoding: chunked
+///
+///     --- chunk ---
+///     Hello
+///     --- chunk ---
+///     World
+///     --- trailers ---
+///     Expires: never
+/// ");
+///
+/// assert_eq!(
+///     raw,
+///     b"POST /hello HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n5\r\nWorld\r\n0\r\nExpires: never\r\n\r\n"
+/// );
+/// ```
+#[proc_macro_error]
+#[proc_macro]
+pub fn raw_request(input: TokenStream) -> TokenStream {
+    if let Some(expansion) = template::extends(&input, "raw_request") {
+        return expansion.into();
+    }
+
+    let input = get_request(input);
+
+    let raw = raw_request::RawRequest::new(&input);
+
+    quote::quote! {
+        #raw
+    }
+    .into()
+}
+
+/// Declares a template with the headers (and optionally the body) that several requests share.
+/// The template is a `macro_rules!` macro with the given name, so it can be used after it is declared in the same module, or in other modules with `#[macro_use]`.
+///
+/// A request extends the template with `request!(extends NAME, "...")`, which also works for [request_builder!] and [raw_request!].
+/// The request line goes in the request, and its body replaces the body of the template.
+/// A header of the request replaces the header with the same name from the template, like `=Name: value` does, `+Name: value` adds another value to the header, and `-Name` removes the header.
+/// Comments, directives and file variables from the template are kept.
+///
+/// # Example
+/// ```rust
+/// use http_macros::{request, request_template};
+///
+/// request_template!(
+///     API,
+///     "Host: api.example.com
+///      Authorization: Bearer token
+///      Accept: application/json"
+/// );
+///
+/// let request = request!(
+///    extends API,
+///    r#"POST /users
+///       Accept: text/plain
+///       +Accept: text/html
+///       -Authorization
+///
+///       { "name": "John Doe" }"#);
+///
+/// assert_eq!(request.method(), http::Method::POST);
+/// assert_eq!(request.headers()["Host"], "api.example.com");
+/// assert_eq!(request.headers().get_all("Accept").iter().count(), 2);
+/// assert!(request.headers().get("Authorization").is_none());
+/// ```
+#[proc_macro_error]
+#[proc_macro]
+pub fn request_template(input: TokenStream) -> TokenStream {
+    template::define(input).into()
+}
+
+/// Creates a function for every operation with an `operationId` in an OpenAPI document.
+/// Each function is named after the `operationId` in snake case and returns the request made from the examples in the document.
+/// The path is relative to the directory with the `Cargo.toml`.
+///
+/// The examples of the parameters come from their `example`, their first `examples`, or the `example` or `default` of their schema.
+/// The body is the example of the first media type with one, where JSON is preferred.
+/// A required parameter or body without an example gives a compile error.
+/// This needs the `openapi` feature.
+///
+/// # Example
+/// ```rust,ignore
+/// mod api {
+///     http_macros::openapi_requests!("openapi.yaml");
+/// }
+///
+/// let request = api::create_user();
+///
+/// assert_eq!(request.method(), http::Method::POST);
+/// assert_eq!(request.uri().path(), "/users");
+/// ```
+#[proc_macro_error]
+#[proc_macro]
+pub fn openapi_requests(input: TokenStream) -> TokenStream {
+    let path = match syn::parse::<syn::LitStr>(input) {
+        Ok(lit) => lit.value(),
+        Err(_) => abort!(
+            Span::call_site(),
+            "Missing path to the OpenAPI document";
+            help = "Try `openapi_requests!(\"openapi.yaml\")`"
+        ),
+    };
+
+    let requests = openapi_requests::OpenApiRequests::new(&path);
+
+    quote::quote! {
+        #requests
+    }
+    .into()
+}
+
 /// Get the actual request from the macro input
 fn get_request(input: TokenStream) -> String {
+    if let Some((template, request)) = template::split_template(&input) {
+        return template::merge(&get_request(template), &get_request(request));
+    }
+
     // `TokenStream` eats up the space characters. However, to match the RFC 7230 spec we need each header to be on a new line.
     // So to preserve the new lines, the input needs to be a string literal when the input is a multi-line string.
     // So check if this input is a string literal or not
@@ -119,9 +694,11 @@ fn get_request(input: TokenStream) -> String {
         proc_macro::TokenTree::Literal(lit) => {
             // Remove the quotes from the string literal
             // And trim the leading and trailing whitespaces
-            lit.to_string()
-                .trim_start_matches("r#")
-                .trim_end_matches('#')
+            let lit = lit.to_string();
+            let lit = lit.strip_prefix('r').unwrap_or(&lit);
+            let hashes = lit.len() - lit.trim_start_matches('#').len();
+
+            lit[hashes..lit.len() - hashes]
                 .trim_matches('"')
                 .lines()
                 .map(|line| line.trim())
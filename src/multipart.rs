@@ -0,0 +1,244 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::{files::Include, headers, variables::Variables};
+
+/// The marker line that starts a new part, followed by the parameters of its `Content-Disposition`.
+const PART_MARKER: &str = "part";
+
+/// The boundary that is tried first when the `Content-Type` does not have one.
+const DEFAULT_BOUNDARY: &str = "http-macros-boundary";
+
+/// Returns whether the `Content-Type` is `multipart/form-data`.
+pub fn is_multipart(headers: &[(String, String)]) -> bool {
+    headers::media_type(headers).is_some_and(|media_type| media_type == "multipart/form-data")
+}
+
+/// Encodes the parts written as `--- part name="..." ---` sections.
+/// A boundary that does not appear in any part is added to the `Content-Type` header when it does not have one.
+pub fn encode(
+    text: &str,
+    headers: &mut [(String, String)],
+    variables: &Variables,
+    files: &mut Vec<String>,
+) -> Vec<u8> {
+    let parts: Vec<Vec<u8>> = split_parts(text)
+        .into_iter()
+        .map(|(parameters, lines)| write_part(parameters, &lines, variables, files))
+        .collect();
+
+    let boundary = match headers::content_type_parameter(headers, "boundary") {
+        Some(boundary) => {
+            if parts.iter().any(|part| contains(part, boundary)) {
+                abort!(
+                    Span::call_site(),
+                    "The boundary `{}` appears in a part", boundary;
+                    help = "Remove the boundary from the `Content-Type` header to have one picked automatically"
+                );
+            }
+
+            boundary.to_string()
+        }
+        None => {
+            let boundary = (0..)
+                .map(|n| match n {
+                    0 => DEFAULT_BOUNDARY.to_string(),
+                    n => format!("{DEFAULT_BOUNDARY}-{n}"),
+                })
+                .find(|boundary| !parts.iter().any(|part| contains(part, boundary)))
+                .expect("the parts are finite, so some boundary does not appear in them");
+
+            // Safe to unwrap since the media type of the `Content-Type` header is `multipart/form-data`
+            let (_, content_type) = headers
+                .iter_mut()
+                .find(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+                .unwrap();
+            content_type.push_str(&format!("; boundary={boundary}"));
+
+            boundary
+        }
+    };
+
+    let mut data = Vec::new();
+
+    for part in parts {
+        data.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        data.extend_from_slice(&part);
+        data.extend_from_slice(b"\r\n");
+    }
+
+    data.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+    data
+}
+
+/// Splits the text on the part markers into the `Content-Disposition` parameters and lines of each part.
+fn split_parts(text: &str) -> Vec<(&str, Vec<&str>)> {
+    let mut parts: Vec<(&str, Vec<&str>)> = Vec::new();
+
+    for line in text.lines() {
+        let parameters = line
+            .trim()
+            .strip_prefix("--- ")
+            .and_then(|line| line.strip_suffix(" ---"))
+            .and_then(|line| line.strip_prefix(PART_MARKER))
+            .filter(|parameters| parameters.is_empty() || parameters.starts_with(' '));
+
+        match (parameters, parts.last_mut()) {
+            (Some(parameters), _) => parts.push((parameters.trim(), Vec::new())),
+            (None, Some((_, lines))) => lines.push(line),
+            (None, None) if line.trim().is_empty() => {}
+            (None, None) => abort!(
+                Span::call_site(),
+                "Multipart content before the first part";
+                help = "Start each part with a `--- part name=\"field\" ---` line"
+            ),
+        }
+    }
+
+    parts
+}
+
+/// Writes the headers and content of a part.
+/// Lines before the first blank line are the headers, and `< path` or `<@ path` as the content includes a file.
+fn write_part(
+    parameters: &str,
+    lines: &[&str],
+    variables: &Variables,
+    files: &mut Vec<String>,
+) -> Vec<u8> {
+    if !parameters
+        .split(';')
+        .any(|parameter| parameter.trim().starts_with("name="))
+    {
+        abort!(
+            Span::call_site(),
+            "Missing name for the part `--- part {} ---`", parameters;
+            help = "Try `--- part name=\"field\" ---` or `--- part name=\"file\"; filename=\"file.txt\" ---`"
+        );
+    }
+
+    // Blank lines between the parts are not part of the content
+    let end = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |end| end + 1);
+    let lines = &lines[..end];
+
+    let (head, content) = match lines.iter().position(|line| line.is_empty()) {
+        Some(blank) => (&lines[..blank], &lines[blank + 1..]),
+        None => (&[][..], lines),
+    };
+
+    let mut part_headers = Vec::from([(
+        "Content-Disposition".to_string(),
+        format!("form-data; {parameters}"),
+    )]);
+
+    for line in head {
+        let Some((name, value)) = line.split_once(':') else {
+            abort!(
+                Span::call_site(),
+                "Malformed part header `{}`", line;
+                help = "The headers of a part end at the first blank line. Start the part with a blank line when its content has blank lines"
+            );
+        };
+
+        part_headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let content = match content {
+        [line] => match Include::parse(line) {
+            Some(include) => include.read(variables, files),
+            None => line.as_bytes().to_vec(),
+        },
+        lines => lines.join("\n").into_bytes(),
+    };
+
+    // Files are sent as bytes unless the part says otherwise
+    if parameters.contains("filename=") && headers::get(&part_headers, "Content-Type").is_none() {
+        part_headers.push((
+            "Content-Type".to_string(),
+            "application/octet-stream".to_string(),
+        ));
+    }
+
+    let mut part = Vec::new();
+
+    for (name, value) in part_headers {
+        part.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+    }
+
+    part.extend_from_slice(b"\r\n");
+    part.extend_from_slice(&content);
+
+    part
+}
+
+/// Returns whether the boundary appears in the data.
+fn contains(data: &[u8], boundary: &str) -> bool {
+    data.windows(boundary.len())
+        .any(|window| window == boundary.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content_type(value: &str) -> Vec<(String, String)> {
+        Vec::from([("Content-Type".to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn parts() {
+        let mut headers = content_type("multipart/form-data");
+        let actual = encode(
+            "--- part name=\"title\" ---\nMy picture\n\n--- part name=\"notes\"; filename=\"notes.txt\" ---\nContent-Type: text/plain\n\nFirst\n\nSecond",
+            &mut headers,
+            &Variables::default(),
+            &mut Vec::new(),
+        );
+
+        assert_eq!(
+            String::from_utf8(actual).unwrap(),
+            "--http-macros-boundary\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nMy picture\r\n--http-macros-boundary\r\nContent-Disposition: form-data; name=\"notes\"; filename=\"notes.txt\"\r\nContent-Type: text/plain\r\n\r\nFirst\n\nSecond\r\n--http-macros-boundary--\r\n"
+        );
+        assert_eq!(
+            headers,
+            content_type("multipart/form-data; boundary=http-macros-boundary")
+        );
+    }
+
+    #[test]
+    fn unique_boundary() {
+        let mut headers = content_type("multipart/form-data");
+        let actual = encode(
+            "--- part name=\"text\" ---\nhttp-macros-boundary http-macros-boundary-1",
+            &mut headers,
+            &Variables::default(),
+            &mut Vec::new(),
+        );
+
+        assert!(actual.starts_with(b"--http-macros-boundary-2\r\n"));
+        assert_eq!(
+            headers,
+            content_type("multipart/form-data; boundary=http-macros-boundary-2")
+        );
+    }
+
+    #[test]
+    fn given_boundary() {
+        let mut headers = content_type("multipart/form-data; boundary=\"xyz\"");
+        let actual = encode(
+            "--- part name=\"empty\" ---",
+            &mut headers,
+            &Variables::default(),
+            &mut Vec::new(),
+        );
+
+        assert_eq!(
+            actual,
+            b"--xyz\r\nContent-Disposition: form-data; name=\"empty\"\r\n\r\n\r\n--xyz--\r\n"
+        );
+    }
+}
@@ -0,0 +1,496 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+use serde_json::Value;
+
+use crate::{directives::Directives, files, headers, json, schema};
+
+/// The environment variable which names the OpenAPI document to check every request against.
+pub const ENV_VAR: &str = "HTTP_MACROS_OPENAPI";
+
+/// Headers which are part of HTTP itself, so they don't need to be defined in the OpenAPI document.
+const STANDARD_HEADERS: &[&str] = &[
+    "accept",
+    "accept-charset",
+    "accept-encoding",
+    "accept-language",
+    "authorization",
+    "cache-control",
+    "connection",
+    "content-encoding",
+    "content-language",
+    "content-length",
+    "content-type",
+    "cookie",
+    "date",
+    "expect",
+    "forwarded",
+    "from",
+    "host",
+    "if-match",
+    "if-modified-since",
+    "if-none-match",
+    "if-range",
+    "if-unmodified-since",
+    "max-forwards",
+    "origin",
+    "pragma",
+    "proxy-authorization",
+    "range",
+    "referer",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "user-agent",
+    "via",
+];
+
+/// A parsed request to check against the OpenAPI document.
+pub struct Check<'a> {
+    pub method: &'a str,
+    pub uri: &'a str,
+    pub headers: &'a [(String, String)],
+    /// The body as text, or `None` when the body cannot be checked.
+    pub body: Option<&'a str>,
+}
+
+/// Gets the path of the OpenAPI document from `# @openapi` or the environment variable.
+/// The environment variable is always added to `env`, so that setting it later rebuilds the requests.
+pub fn get_document(directives: &Directives, env: &mut Vec<String>) -> Option<String> {
+    if directives.allows("openapi-mismatch") {
+        return None;
+    }
+
+    if let Some(path) = directives.get("openapi") {
+        return Some(path.to_string());
+    }
+
+    // The environment variable is only used with the feature, so that it does not break crates without it
+    if !cfg!(feature = "openapi") {
+        return None;
+    }
+
+    env.push(ENV_VAR.to_string());
+
+    std::env::var(ENV_VAR).ok().filter(|path| !path.is_empty())
+}
+
+/// The environment variables that every request tracks, which is the variable with the document when the feature is on.
+#[cfg(test)]
+pub fn tracked_env() -> Vec<String> {
+    match cfg!(feature = "openapi") {
+        true => Vec::from([ENV_VAR.to_string()]),
+        false => Vec::new(),
+    }
+}
+
+/// Checks the request against the OpenAPI document in the file.
+/// Returns the full path of the document.
+pub fn validate(path: &str, request: Check) -> String {
+    let (full_path, content) = files::read(path);
+    let document = parse(&content, path);
+
+    let problems = check(&document, &request);
+
+    if !problems.is_empty() {
+        abort!(
+            Span::call_site(),
+            "`{} {}` does not match the OpenAPI document `{}`", request.method, request.uri, path;
+            note = "{}", problems.join("\n");
+            help = "Add `# @allow openapi-mismatch` if the request does not match on purpose"
+        );
+    }
+
+    full_path
+}
+
+/// Parses the OpenAPI document, which can be YAML or JSON.
+#[cfg(feature = "openapi")]
+pub fn parse(content: &str, path: &str) -> Value {
+    match serde_yaml::from_str(content) {
+        Ok(document) => document,
+        Err(error) => abort!(
+            Span::call_site(),
+            "`{}` is not a valid OpenAPI document: {}",
+            path,
+            error
+        ),
+    }
+}
+
+#[cfg(not(feature = "openapi"))]
+pub fn parse(_content: &str, _path: &str) -> Value {
+    abort!(
+        Span::call_site(),
+        "Checking requests against an OpenAPI document needs the `openapi` feature";
+        help = "Add `features = [\"openapi\"]` to the `http-macros` dependency"
+    );
+}
+
+/// Returns everything in the request that does not match the document.
+fn check(document: &Value, request: &Check) -> Vec<String> {
+    let (path, query) = split_uri(request.uri);
+
+    let Some((template, path_item)) = find_path(document, path) else {
+        return Vec::from([format!("The path `{path}` is not defined")]);
+    };
+
+    let method = request.method.to_lowercase();
+    let Some(operation) = path_item.get(&method) else {
+        return Vec::from([format!(
+            "`{template}` has no `{}` operation",
+            request.method
+        )]);
+    };
+
+    let mut problems = Vec::new();
+    let parameters: Vec<&Value> = [path_item, operation]
+        .into_iter()
+        .filter_map(|item| item.get("parameters").and_then(Value::as_array))
+        .flatten()
+        .map(|parameter| resolve(document, parameter))
+        .collect();
+
+    let defined = |location| parameter_names(&parameters, location, false);
+    let required = |location| parameter_names(&parameters, location, true);
+
+    // Query parameters
+    let names: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').map_or(pair, |(name, _)| name))
+        .collect();
+
+    for name in &names {
+        if !defined("query").contains(name) {
+            problems.push(format!("The query parameter `{name}` is not defined"));
+        }
+    }
+
+    for name in required("query") {
+        if !names.contains(&name) {
+            problems.push(format!("The required query parameter `{name}` is missing"));
+        }
+    }
+
+    // Headers
+    let api_key_headers = api_key_headers(document);
+
+    for (name, _) in request.headers {
+        let name = name.to_lowercase();
+
+        let is_defined = STANDARD_HEADERS.contains(&name.as_str())
+            || api_key_headers.contains(&name)
+            || defined("header")
+                .iter()
+                .any(|defined| defined.eq_ignore_ascii_case(&name));
+
+        if !is_defined {
+            problems.push(format!("The header `{name}` is not defined"));
+        }
+    }
+
+    for name in required("header") {
+        if headers::get(request.headers, name).is_none() {
+            problems.push(format!("The required header `{name}` is missing"));
+        }
+    }
+
+    // Body
+    if let Some(request_body) = operation.get("requestBody") {
+        check_body(
+            document,
+            resolve(document, request_body),
+            request,
+            &mut problems,
+        );
+    }
+
+    problems
+}
+
+/// Returns the names of the parameters in the location, like `query` or `header`.
+fn parameter_names<'a>(parameters: &[&'a Value], location: &str, required: bool) -> Vec<&'a str> {
+    parameters
+        .iter()
+        .filter(|parameter| parameter["in"] == location)
+        .filter(|parameter| !required || parameter["required"] == true)
+        .filter_map(|parameter| parameter["name"].as_str())
+        .collect()
+}
+
+/// Checks the body of the request against the `requestBody` of the operation.
+fn check_body(document: &Value, request_body: &Value, request: &Check, problems: &mut Vec<String>) {
+    let Some(body) = request.body else {
+        return;
+    };
+
+    if body.trim().is_empty() {
+        if request_body["required"] == true {
+            problems.push("The required body is missing".to_string());
+        }
+
+        return;
+    }
+
+    let media_type = headers::media_type(request.headers).unwrap_or_default();
+    let Some(content) = request_body["content"].as_object() else {
+        return;
+    };
+
+    let Some((_, media)) = content
+        .iter()
+        .find(|(pattern, _)| media_type_matches(pattern, &media_type))
+    else {
+        problems.push(format!(
+            "The content type `{media_type}` is not one of: {}",
+            content.keys().cloned().collect::<Vec<_>>().join(", ")
+        ));
+        return;
+    };
+
+    let Some(body_schema) = media.get("schema") else {
+        return;
+    };
+
+    if !json::is_json(request.headers) {
+        return;
+    }
+
+    let Ok(instance) = serde_json::from_str::<Value>(body) else {
+        problems.push("The body is not valid JSON".to_string());
+        return;
+    };
+
+    // References in the schema point into the document, so the components are added to the schema to keep them working
+    let mut body_schema = body_schema.clone();
+    if let (Some(map), Some(components)) = (body_schema.as_object_mut(), document.get("components"))
+    {
+        map.insert("components".to_string(), components.clone());
+    }
+
+    problems.extend(
+        schema::check(&body_schema, &instance, "the request body schema")
+            .into_iter()
+            .map(|problem| format!("Body {problem}")),
+    );
+}
+
+/// Splits the URI into its path and query, dropping any scheme and authority.
+fn split_uri(uri: &str) -> (&str, &str) {
+    let uri = match uri.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |start| &rest[start..]),
+        None => uri,
+    };
+
+    uri.split_once('?').unwrap_or((uri, ""))
+}
+
+/// Finds the path item with a template matching the path.
+/// Templates with fewer parameters win, so `/users/me` is preferred over `/users/{id}`.
+fn find_path<'a>(document: &'a Value, path: &str) -> Option<(&'a str, &'a Value)> {
+    let paths = document["paths"].as_object()?;
+    let bases = server_base_paths(document);
+
+    paths
+        .iter()
+        .filter(|(template, _)| {
+            bases.iter().any(|base| {
+                path.strip_prefix(base.as_str())
+                    .is_some_and(|path| template_matches(template, path))
+            })
+        })
+        .min_by_key(|(template, _)| template.matches('{').count())
+        .map(|(template, item)| (template.as_str(), item))
+}
+
+/// Returns the paths of the server URLs, which come before the templates of the paths.
+fn server_base_paths(document: &Value) -> Vec<String> {
+    let mut bases: Vec<String> = document["servers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|server| server["url"].as_str())
+        .map(|url| split_uri(url).0.trim_end_matches('/').to_string())
+        .collect();
+
+    bases.push(String::new());
+    bases
+}
+
+/// Returns whether a path matches a template like `/users/{id}`.
+fn template_matches(template: &str, path: &str) -> bool {
+    let template: Vec<&str> = template.trim_end_matches('/').split('/').collect();
+    let path: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+    template.len() == path.len()
+        && template.iter().zip(path).all(|(template, segment)| {
+            if template.starts_with('{') && template.ends_with('}') {
+                !segment.is_empty()
+            } else {
+                *template == segment
+            }
+        })
+}
+
+/// Returns whether a media type matches a media type range like `application/*`.
+fn media_type_matches(pattern: &str, media_type: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+
+    match pattern.split_once('/') {
+        _ if pattern == "*/*" => true,
+        Some((kind, "*")) => media_type.split('/').next() == Some(kind),
+        _ => pattern == media_type,
+    }
+}
+
+/// Returns the names of the headers used by `apiKey` security schemes in lower case.
+fn api_key_headers(document: &Value) -> Vec<String> {
+    document["components"]["securitySchemes"]
+        .as_object()
+        .into_iter()
+        .flat_map(|schemes| schemes.values())
+        .filter(|scheme| scheme["type"] == "apiKey" && scheme["in"] == "header")
+        .filter_map(|scheme| scheme["name"].as_str())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Follows a `$ref` to a component in the same document.
+pub fn resolve<'a>(document: &'a Value, value: &'a Value) -> &'a Value {
+    match value["$ref"].as_str() {
+        Some(reference) => document
+            .pointer(reference.trim_start_matches('#'))
+            .unwrap_or(value),
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> Value {
+        serde_json::json!({
+            "openapi": "3.1.0",
+            "servers": [{ "url": "https://example.com/api" }],
+            "paths": {
+                "/users/{id}": {
+                    "parameters": [{ "$ref": "#/components/parameters/Id" }],
+                    "get": {
+                        "parameters": [
+                            { "name": "expand", "in": "query" },
+                            { "name": "X-Request-Id", "in": "header", "required": true }
+                        ]
+                    }
+                },
+                "/users/me": { "get": {} },
+                "/users": {
+                    "post": {
+                        "requestBody": {
+                            "required": true,
+                            "content": { "application/json": {} }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "parameters": {
+                    "Id": { "name": "id", "in": "path", "required": true }
+                },
+                "securitySchemes": {
+                    "key": { "type": "apiKey", "in": "header", "name": "X-Api-Key" }
+                }
+            }
+        })
+    }
+
+    fn check_request(method: &str, uri: &str, headers: &[(&str, &str)], body: &str) -> Vec<String> {
+        let headers: Vec<_> = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        check(
+            &document(),
+            &Check {
+                method,
+                uri,
+                headers: &headers,
+                body: Some(body),
+            },
+        )
+    }
+
+    #[test]
+    fn templates() {
+        assert!(template_matches("/users/{id}", "/users/42"));
+        assert!(template_matches("/users/{id}/", "/users/42"));
+        assert!(!template_matches("/users/{id}", "/users"));
+        assert!(!template_matches("/users/{id}", "/users/42/posts"));
+    }
+
+    #[test]
+    fn paths() {
+        let document = document();
+
+        assert_eq!(find_path(&document, "/users/me").unwrap().0, "/users/me");
+        assert_eq!(find_path(&document, "/users/42").unwrap().0, "/users/{id}");
+        assert_eq!(
+            find_path(&document, "/api/users/42").unwrap().0,
+            "/users/{id}"
+        );
+        assert!(find_path(&document, "/posts").is_none());
+    }
+
+    #[test]
+    fn uris() {
+        assert_eq!(split_uri("/users?expand=1"), ("/users", "expand=1"));
+        assert_eq!(split_uri("https://example.com/users"), ("/users", ""));
+        assert_eq!(split_uri("https://example.com"), ("/", ""));
+    }
+
+    #[test]
+    fn valid() {
+        let problems = check_request(
+            "GET",
+            "/users/42?expand=posts",
+            &[
+                ("Host", "example.com"),
+                ("X-Request-Id", "1"),
+                ("X-Api-Key", "secret"),
+            ],
+            "",
+        );
+
+        assert_eq!(problems, Vec::<String>::new());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            check_request("GET", "/posts", &[], ""),
+            ["The path `/posts` is not defined"]
+        );
+        assert_eq!(
+            check_request("DELETE", "/users/42", &[], ""),
+            ["`/users/{id}` has no `DELETE` operation"]
+        );
+        assert_eq!(
+            check_request("GET", "/users/42?page=2", &[("X-Debug", "1")], ""),
+            [
+                "The query parameter `page` is not defined",
+                "The header `x-debug` is not defined",
+                "The required header `X-Request-Id` is missing",
+            ]
+        );
+        assert_eq!(
+            check_request("POST", "/users", &[], ""),
+            ["The required body is missing"]
+        );
+        assert_eq!(
+            check_request("POST", "/users", &[("Content-Type", "text/plain")], "Hi"),
+            ["The content type `text/plain` is not one of: application/json"]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+use quote::{format_ident, quote, ToTokens};
+use serde_json::Value;
+
+use crate::{files, json, openapi, request::Request, token_helpers::get_tracking, url};
+
+/// The methods which can have an operation in an OpenAPI path item.
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Represents the example requests of every operation in an OpenAPI document.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct OpenApiRequests {
+    file: String,
+    operations: Vec<Operation>,
+}
+
+/// An operation with an `operationId` and the request made from its examples.
+#[derive(Debug, PartialEq, Eq, Default)]
+struct Operation {
+    name: String,
+    doc: String,
+    request: Request,
+}
+
+impl OpenApiRequests {
+    pub fn new(path: &str) -> Self {
+        let (file, content) = files::read(path);
+        let document = openapi::parse(&content, path);
+
+        let operations = document["paths"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .flat_map(|(template, path_item)| {
+                let document = &document;
+
+                METHODS.iter().filter_map(move |method| {
+                    let operation = path_item.get(*method)?;
+                    let id = operation["operationId"].as_str()?;
+                    let input = get_request(document, template, method, path_item, operation, id);
+
+                    Some(Operation {
+                        name: snake_case(id),
+                        doc: operation["summary"]
+                            .as_str()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| format!("`{} {template}`", method.to_uppercase())),
+                        request: Request::new(&input),
+                    })
+                })
+            })
+            .collect();
+
+        Self { file, operations }
+    }
+}
+
+impl ToTokens for OpenApiRequests {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let tracking = get_tracking(std::slice::from_ref(&self.file), &[]);
+        let functions = self.operations.iter().map(|operation| {
+            let Operation { name, doc, request } = operation;
+            let name = format_ident!("{}", name);
+            let body_type = request.body_type();
+
+            quote! {
+                #[doc = #doc]
+                pub fn #name() -> http::Request<#body_type> {
+                    #request
+                    .unwrap()
+                }
+            }
+        });
+
+        quote! {
+            #tracking
+            #(#functions)*
+        }
+        .to_tokens(tokens);
+    }
+}
+
+/// Writes the request of an operation from the examples in the document.
+fn get_request(
+    document: &Value,
+    template: &str,
+    method: &str,
+    path_item: &Value,
+    operation: &Value,
+    id: &str,
+) -> String {
+    let parameters: Vec<&Value> = [path_item, operation]
+        .into_iter()
+        .filter_map(|item| item["parameters"].as_array())
+        .flatten()
+        .map(|parameter| openapi::resolve(document, parameter))
+        .collect();
+
+    let mut path = template.to_string();
+    let mut query = Vec::new();
+    let mut headers = Vec::new();
+
+    for parameter in parameters {
+        let name = parameter["name"].as_str().unwrap_or_default();
+        let location = parameter["in"].as_str().unwrap_or_default();
+
+        let Some(example) = parameter_example(document, parameter) else {
+            if location == "path" || parameter["required"] == true {
+                abort!(
+                    Span::call_site(),
+                    "The required parameter `{}` of `{}` has no example", name, id;
+                    help = "Add an `example` to the parameter in the OpenAPI document"
+                );
+            }
+
+            continue;
+        };
+
+        let value = to_text(example);
+
+        match location {
+            "path" => path = path.replace(&format!("{{{name}}}"), &url::encode_component(&value)),
+            "query" => query.push(format!("{name}={}", url::encode_component(&value))),
+            "header" => headers.push(format!("{name}: {value}")),
+            _ => {}
+        }
+    }
+
+    let mut input = format!("{} {path}", method.to_uppercase());
+
+    if !query.is_empty() {
+        input.push('?');
+        input.push_str(&query.join("&"));
+    }
+
+    for header in headers {
+        input.push('\n');
+        input.push_str(&header);
+    }
+
+    if let Some(request_body) = operation.get("requestBody") {
+        let request_body = openapi::resolve(document, request_body);
+
+        match body_example(document, request_body) {
+            Some((media_type, example)) => {
+                input.push_str(&format!("\nContent-Type: {media_type}\n\n"));
+
+                if json::is_json(&[("Content-Type".to_string(), media_type.to_string())]) {
+                    input.push_str(&example.to_string());
+                } else {
+                    input.push_str(&to_text(example));
+                }
+            }
+            None if request_body["required"] == true => abort!(
+                Span::call_site(),
+                "The required body of `{}` has no example", id;
+                help = "Add an `example` to the request body in the OpenAPI document"
+            ),
+            None => {}
+        }
+    }
+
+    input
+}
+
+/// Gets the example of a parameter, from the parameter itself or from its schema.
+fn parameter_example<'a>(document: &'a Value, parameter: &'a Value) -> Option<&'a Value> {
+    example(document, parameter).or_else(|| {
+        let schema = openapi::resolve(document, parameter.get("schema")?);
+
+        schema.get("example").or_else(|| schema.get("default"))
+    })
+}
+
+/// Gets the first media type of a request body with an example, preferring JSON.
+fn body_example<'a>(document: &'a Value, request_body: &'a Value) -> Option<(&'a str, &'a Value)> {
+    let content = request_body["content"].as_object()?;
+
+    let mut media_types: Vec<_> = content.iter().collect();
+    media_types.sort_by_key(|(media_type, _)| !media_type.contains("json"));
+
+    media_types.into_iter().find_map(|(media_type, media)| {
+        let example = example(document, media)
+            .or_else(|| openapi::resolve(document, media.get("schema")?).get("example"))?;
+
+        Some((media_type.as_str(), example))
+    })
+}
+
+/// Gets the `example`, or the value of the first of the `examples`.
+fn example<'a>(document: &'a Value, value: &'a Value) -> Option<&'a Value> {
+    value.get("example").or_else(|| {
+        let first = value["examples"].as_object()?.values().next()?;
+
+        openapi::resolve(document, first).get("value")
+    })
+}
+
+/// Writes an example value as plain text, with arrays separated by commas.
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values.iter().map(to_text).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+/// Turns an `operationId` like `getUserById` into a function name like `get_user_by_id`.
+fn snake_case(id: &str) -> String {
+    let mut name = String::new();
+
+    for (i, c) in id.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !name.ends_with('_') {
+                name.push('_');
+            }
+
+            name.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            name.push(c);
+        } else if !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(snake_case("getUserById"), "get_user_by_id");
+        assert_eq!(snake_case("list-users"), "list_users");
+        assert_eq!(snake_case("users.create"), "users_create");
+        assert_eq!(snake_case("2fa"), "_2fa");
+    }
+
+    #[test]
+    fn request() {
+        let document = serde_json::json!({
+            "paths": {
+                "/users/{id}": {
+                    "parameters": [{ "name": "id", "in": "path", "example": 42 }],
+                    "put": {
+                        "operationId": "updateUser",
+                        "parameters": [
+                            { "name": "fields", "in": "query", "schema": { "example": ["name", "age"] } },
+                            { "name": "X-Request-Id", "in": "header", "examples": { "one": { "value": "abc" } } },
+                            { "name": "debug", "in": "query" }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "text/plain": { "example": "Hello" },
+                                "application/json": { "example": { "name": "John Doe" } }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        let path_item = &document["paths"]["/users/{id}"];
+
+        assert_eq!(
+            get_request(
+                &document,
+                "/users/{id}",
+                "put",
+                path_item,
+                &path_item["put"],
+                "updateUser"
+            ),
+            "PUT /users/42?fields=name%2Cage\nX-Request-Id: abc\nContent-Type: application/json\n\n{\"name\":\"John Doe\"}"
+        );
+    }
+}
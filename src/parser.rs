@@ -1,6 +1,8 @@
 use proc_macro::Span;
 use proc_macro_error::abort;
 
+use crate::{directives::Directives, files::Include, headers};
+
 /// A simple tokenizer over some bytes.
 struct Tokenizer<'a> {
     buf: &'a [u8],
@@ -9,11 +11,12 @@ struct Tokenizer<'a> {
 
 impl Tokenizer<'_> {
     /// Creates a new tokenizer from a buffer.
-    fn new(buf: &[u8]) -> Tokenizer {
+    fn new(buf: &[u8]) -> Tokenizer<'_> {
         Tokenizer { buf, pos: 0 }
     }
 
     /// Returns the next token in the buffer. A token is seperated by a space or a new line.
+    /// Spaces inside a `{{...}}` variable do not end the token, so that `{{$processEnv BASE_URL}}/hello` is one token.
     fn next(&mut self) -> Option<String> {
         let start = self.pos;
         let mut end = self.pos;
@@ -23,6 +26,21 @@ impl Tokenizer<'_> {
                 break;
             }
 
+            if self.buf[end..].starts_with(b"{{") {
+                let line_end = self.buf[end..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map_or(self.buf.len(), |position| end + position);
+
+                if let Some(close) = self.buf[end..line_end]
+                    .windows(2)
+                    .position(|window| window == b"}}")
+                {
+                    end += close + 2;
+                    continue;
+                }
+            }
+
             end += 1;
         }
 
@@ -75,20 +93,30 @@ impl<'a> Tokenizer<'a> {
 
 /// A simple HTTP request parser.
 pub struct Parser<'a> {
+    pub directives: Directives,
+    /// The file variables declared with `@name = value` before the request line.
+    pub variables: Vec<(String, String)>,
     pub method: String,
     pub uri: String,
     pub version: Option<String>,
     pub headers: Vec<(String, String)>,
     pub body: &'a [u8],
+    /// The file to use as the body when the body is a `< path` or `<@ path` line.
+    pub include: Option<Include>,
 }
 
 impl<'a> Parser<'a> {
     /// Creates a new parser from a buffer.
     pub fn new(buf: &'a [u8]) -> Parser<'a> {
+        let (directives, variables, buf) = parse_directives(buf);
         let mut tokenizer = Tokenizer::new(buf);
 
         let Some(method) = tokenizer.next() else {
-            unreachable!("already checked in `get_request` that at least something exists");
+            abort!(
+                Span::call_site(),
+                "Missing request line";
+                help = "Add a request line after the comments, like `GET /hello`"
+            );
         };
 
         let Some(uri) = tokenizer.next() else {
@@ -109,11 +137,14 @@ impl<'a> Parser<'a> {
         // All the rest (headers and body) is optional
         if tokenizer.is_end() {
             return Self {
+                directives,
+                variables,
                 method,
                 uri,
                 version,
                 headers: vec![],
                 body: tokenizer.rest(),
+                include: None,
             };
         }
 
@@ -156,17 +187,63 @@ impl<'a> Parser<'a> {
                 }
             }
 
-            headers.push((name, value.join(" ")));
+            headers::push(&mut headers, &name, value.join(" "));
         }
 
+        let body = tokenizer.rest();
+
         Self {
+            directives,
+            variables,
             method,
             uri,
             version,
             headers,
-            body: tokenizer.rest(),
+            body,
+            include: parse_include(body),
+        }
+    }
+}
+
+/// Parses a body which is only a `< path` or `<@ path` line.
+fn parse_include(body: &[u8]) -> Option<Include> {
+    let body = std::str::from_utf8(body).ok()?.trim();
+
+    if body.contains('\n') {
+        return None;
+    }
+
+    Include::parse(body)
+}
+
+/// Splits off the comment lines (starting with `#`) and the `@name = value` lines before the request line.
+/// Collects the directives in the comments and the file variables from the `@` lines.
+fn parse_directives(mut buf: &[u8]) -> (Directives, Vec<(String, String)>, &[u8]) {
+    let mut directives = Directives::default();
+    let mut variables = Vec::new();
+
+    while let Some(b'#' | b'@' | b'\n') = buf.first() {
+        let end = buf.iter().position(|b| *b == b'\n').unwrap_or(buf.len());
+        let line = std::str::from_utf8(&buf[..end]).unwrap();
+
+        if let Some(declaration) = line.strip_prefix('@') {
+            let Some((name, value)) = declaration.split_once('=') else {
+                abort!(
+                    Span::call_site(),
+                    "Invalid file variable `{}`", line;
+                    help = "Declare a file variable like `@host = localhost:8080`"
+                );
+            };
+
+            variables.push((name.trim().to_string(), value.trim().to_string()));
+        } else {
+            directives.push_line(line);
         }
+
+        buf = buf.get(end + 1..).unwrap_or_default();
     }
+
+    (directives, variables, buf)
 }
 
 #[cfg(test)]
@@ -190,6 +267,22 @@ mod tests {
         assert_eq!(tokenizer.next(), None);
     }
 
+    #[test]
+    fn tokenizer_variables() {
+        let buf = b"GET {{$processEnv BASE_URL}}/hello {{ version\nHost: {{host}}";
+        let mut tokenizer = Tokenizer::new(buf);
+
+        assert_eq!(tokenizer.next(), Some("GET".to_string()));
+        assert_eq!(
+            tokenizer.next(),
+            Some("{{$processEnv BASE_URL}}/hello".to_string())
+        );
+        assert_eq!(tokenizer.next(), Some("{{".to_string()));
+        assert_eq!(tokenizer.next(), Some("version".to_string()));
+        assert_eq!(tokenizer.next(), Some("Host:".to_string()));
+        assert_eq!(tokenizer.next(), Some("{{host}}".to_string()));
+    }
+
     #[test]
     fn parser_simple() {
         let buf = b"GET /hello";
@@ -255,4 +348,60 @@ mod tests {
         );
         assert_eq!(parser.body, b"{ \"note\": \"Buy milk\" }");
     }
+
+    #[test]
+    fn parser_with_directives() {
+        let buf = b"# Some comment\n# @content-length\nPOST /hello\n\nHello";
+        let parser = Parser::new(buf);
+
+        assert!(parser.directives.has("content-length"));
+        assert_eq!(parser.method, "POST");
+        assert_eq!(parser.uri, "/hello");
+        assert_eq!(parser.body, b"Hello");
+    }
+
+    #[test]
+    fn parser_with_variables() {
+        let buf =
+            b"@host = localhost:8080\n# @content-length\n@url = http://{{host}}\nGET {{url}}/hello";
+        let parser = Parser::new(buf);
+
+        assert_eq!(
+            parser.variables,
+            Vec::from([
+                ("host".to_string(), "localhost:8080".to_string()),
+                ("url".to_string(), "http://{{host}}".to_string())
+            ])
+        );
+        assert!(parser.directives.has("content-length"));
+        assert_eq!(parser.uri, "{{url}}/hello");
+    }
+
+    #[test]
+    fn parser_with_include() {
+        let parser = Parser::new(
+            b"POST /hello
+Content-Type: application/json
+
+<@ ./payload.json
+",
+        );
+
+        assert_eq!(
+            parser.include,
+            Some(Include {
+                path: "./payload.json".to_string(),
+                substitute: true
+            })
+        );
+
+        let parser = Parser::new(
+            b"POST /hello
+
+< ./payload.json
+Hello",
+        );
+
+        assert_eq!(parser.include, None);
+    }
 }
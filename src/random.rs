@@ -0,0 +1,171 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::{directives::Directives, dynamic::Part};
+
+/// Makes the random values when the macro runs, so that they are the same on every test run.
+/// This is the SplitMix64 generator, which is fine for test data but not for secrets.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Seeded(u64);
+
+impl Seeded {
+    /// Gets the generator from the `# @random-seed 42` directive.
+    pub fn new(directives: &Directives) -> Option<Self> {
+        let seed = directives.get("random-seed")?;
+
+        match seed.parse() {
+            Ok(seed) => Some(Self(seed)),
+            Err(_) => abort!(
+                Span::call_site(),
+                "Invalid random seed `{}`", seed;
+                help = "The seed is a whole number, like `# @random-seed 42`"
+            ),
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+        z ^ (z >> 31)
+    }
+}
+
+/// Makes a version 4 UUID for `{{$guid}}`.
+pub fn guid(arguments: &str, seeded: &mut Option<Seeded>) -> Part {
+    no_arguments("guid", arguments);
+
+    if let Some(seeded) = seeded {
+        let value = u128::from(seeded.next()) << 64 | u128::from(seeded.next());
+        let value = value & !(0xf << 76) | 0x4 << 76;
+        let value = value & !(0x3 << 62) | 0x2 << 62;
+
+        return Part::Text(format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            value >> 96,
+            value >> 80 & 0xffff,
+            value >> 64 & 0xffff,
+            value >> 48 & 0xffff,
+            value & 0xffff_ffff_ffff
+        ));
+    }
+
+    let random = runtime_random();
+
+    Part::Code(quote! {
+        {
+            let value = u128::from(#random) << 64 | u128::from(#random);
+            let value = value & !(0xf << 76) | 0x4 << 76;
+            let value = value & !(0x3 << 62) | 0x2 << 62;
+
+            ::std::format!(
+                "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+                value >> 96,
+                value >> 80 & 0xffff,
+                value >> 64 & 0xffff,
+                value >> 48 & 0xffff,
+                value & 0xffff_ffff_ffff
+            )
+        }
+    })
+}
+
+/// Makes a whole number from `min` up to, but not including, `max` for `{{$randomInt min max}}`.
+pub fn random_int(arguments: &str, seeded: &mut Option<Seeded>) -> Part {
+    let bounds: Vec<i64> = arguments
+        .split_whitespace()
+        .map(|bound| bound.parse())
+        .collect::<Result<_, _>>()
+        .unwrap_or_default();
+
+    let &[min, max] = bounds.as_slice() else {
+        abort!(
+            Span::call_site(),
+            "Invalid arguments `{}` for `$randomInt`", arguments;
+            help = "Try `{{$randomInt 1 100}}`"
+        );
+    };
+
+    if min >= max {
+        abort!(
+            Span::call_site(),
+            "The minimum {} of `$randomInt` is not less than the maximum {}", min, max;
+            help = "The maximum is not included, so try `{{$randomInt {} {}}}`", min, min + 1
+        );
+    }
+
+    let range = max.abs_diff(min);
+
+    if let Some(seeded) = seeded {
+        return Part::Text(min.wrapping_add((seeded.next() % range) as i64).to_string());
+    }
+
+    let random = runtime_random();
+
+    Part::Code(quote! {
+        (#min).wrapping_add((#random % #range) as i64).to_string()
+    })
+}
+
+/// Writes the code for a random `u64` when the request is made.
+/// Each `RandomState` has new keys, which is random enough for test data without needing another crate.
+fn runtime_random() -> proc_macro2::TokenStream {
+    quote! {
+        ::std::hash::Hasher::finish(&::std::hash::BuildHasher::build_hasher(
+            &::std::collections::hash_map::RandomState::new(),
+        ))
+    }
+}
+
+/// Aborts when a variable which does not take arguments has some.
+fn no_arguments(name: &str, arguments: &str) {
+    if !arguments.is_empty() {
+        abort!(
+            Span::call_site(),
+            "`${}` does not take arguments", name;
+            help = "Try `{{${}}}`", name
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(part: Part) -> String {
+        match part {
+            Part::Text(text) => text,
+            Part::Code(code) => panic!("expected text, got {code}"),
+        }
+    }
+
+    #[test]
+    fn seeded() {
+        let mut directives = Directives::default();
+        directives.push_line("# @random-seed 42");
+        let mut seeded = Seeded::new(&directives);
+
+        let first = text(guid("", &mut seeded));
+        assert_eq!(first.len(), 36);
+        assert_eq!(&first[14..15], "4");
+        assert!(matches!(&first[19..20], "8" | "9" | "a" | "b"));
+
+        for _ in 0..100 {
+            let value: i64 = text(random_int("-5 5", &mut seeded)).parse().unwrap();
+            assert!((-5..5).contains(&value));
+        }
+
+        let mut again = Seeded::new(&directives);
+        assert_eq!(text(guid("", &mut again)), first);
+    }
+
+    #[test]
+    fn runtime() {
+        assert!(matches!(guid("", &mut None), Part::Code(_)));
+        assert!(matches!(random_int("1 10", &mut None), Part::Code(_)));
+    }
+}
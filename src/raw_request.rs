@@ -0,0 +1,49 @@
+use quote::{quote, ToTokens};
+
+use crate::{request::Request, token_helpers::get_tracking};
+
+/// Represents a request in the raw form it is sent over the wire.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct RawRequest(Request);
+
+impl RawRequest {
+    pub fn new(input: &str) -> Self {
+        Self(Request::new(input))
+    }
+}
+
+impl ToTokens for RawRequest {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let wire = proc_macro2::Literal::byte_string(&self.0.to_wire());
+
+        let raw = quote! {
+            #wire.to_vec()
+        };
+
+        let (files, env) = self.0.tracked();
+
+        match get_tracking(files, env) {
+            Some(tracking) => quote! { { #tracking #raw } }.to_tokens(tokens),
+            None => raw.to_tokens(tokens),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output() {
+        let input = RawRequest::new("GET /health\nHost: localhost:8000");
+        let raw = quote! {
+            b"GET /health HTTP/1.1\r\nHost: localhost:8000\r\n\r\n".to_vec()
+        };
+        let expected = match get_tracking(&[], &crate::openapi::tracked_env()) {
+            Some(tracking) => quote! { { #tracking #raw } },
+            None => raw,
+        };
+
+        assert_eq!(input.to_token_stream().to_string(), expected.to_string());
+    }
+}
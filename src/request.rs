@@ -1,8 +1,14 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
 use quote::{quote, ToTokens};
 
 use crate::{
+    auth,
+    aws::{self, Signer},
+    body::Body,
+    content_length, dynamic, environment, graphql, headers, openapi,
     parser::Parser,
-    token_helpers::{get_headers, get_version},
+    token_helpers::{get_headers, get_tracking, get_version},
 };
 
 /// Represents a HTTP request (which has a body).
@@ -12,39 +18,168 @@ pub struct Request {
     uri: String,
     version: Option<String>,
     headers: Vec<(String, String)>,
-    body: Vec<u8>,
+    body: Body,
+    signer: Option<Signer>,
+    files: Vec<String>,
+    env: Vec<String>,
 }
 
 impl Request {
     pub fn new(input: &str) -> Self {
         let buf = input.as_bytes();
         let Parser {
+            directives,
+            variables: file_variables,
             method,
-            uri,
+            mut uri,
             version,
-            headers,
+            mut headers,
             body,
+            include,
         } = Parser::new(buf);
 
+        headers::check_duplicates(&directives, &headers);
+
+        let mut files = Vec::new();
+        let mut env = Vec::new();
+        let environment = environment::load(&directives, input, file_variables);
+        let variables = &environment.variables;
+        variables.substitute_request(&mut uri, &mut headers);
+        let included =
+            include.map(|include| (include.substitute, include.read(variables, &mut files)));
+
+        // The text of the body has dynamic variables too, unless it is a file which is included as it is
+        let mut text = match &included {
+            None => std::str::from_utf8(body)
+                .ok()
+                .map(|body| variables.substitute(body)),
+            Some((true, content)) => std::str::from_utf8(content).ok().map(str::to_string),
+            Some((false, _)) => None,
+        };
+        dynamic::resolve(
+            &mut uri,
+            &mut headers,
+            text.as_mut(),
+            &directives,
+            &mut files,
+            &mut env,
+        );
+
+        let mut body = Body::new(
+            text.as_ref()
+                .map(String::as_bytes)
+                .or(included.as_ref().map(|(_, content)| content.as_slice()))
+                .unwrap_or(body),
+            &mut headers,
+            &directives,
+            variables,
+            &mut files,
+        );
+
+        if text.as_deref().is_some_and(dynamic::is_runtime) {
+            body.make_at_runtime(&headers, &directives);
+        }
+
+        environment.track(&mut files, &mut env);
+
+        graphql::remove_marker(&mut headers);
+        content_length::apply(&directives, &mut headers, &body);
+
+        if let Some(document) = openapi::get_document(&directives, &mut env) {
+            let check = openapi::Check {
+                method: &method,
+                uri: &uri,
+                headers: &headers,
+                body: (!body.binary)
+                    .then(|| std::str::from_utf8(&body.data).ok())
+                    .flatten(),
+            };
+
+            files.push(openapi::validate(&document, check));
+        }
+
+        // The headers added by the signature are not in the OpenAPI document, so the request is signed last
+        let signer = aws::sign(&method, &uri, &directives, &mut headers, &body);
+
         Self {
             method,
             uri,
             version,
             headers,
-            body: body.to_vec(),
+            body,
+            signer,
+            files,
+            env,
+        }
+    }
+
+    /// Returns the files and environment variables that were read to make the request.
+    pub fn tracked(&self) -> (&[String], &[String]) {
+        (&self.files, &self.env)
+    }
+
+    /// Returns the type of the body of the request in the generated code.
+    pub fn body_type(&self) -> proc_macro2::TokenStream {
+        self.body.type_tokens()
+    }
+
+    /// Writes the request the way it is sent over the wire for HTTP/1.x.
+    pub fn to_wire(&self) -> Vec<u8> {
+        if dynamic::is_runtime(&self.uri) {
+            abort!(
+                Span::call_site(),
+                "The URI is only known when the request is made";
+                help = "Use `request!` for requests with values that are made when the request is made"
+            );
+        }
+
+        let version = self.version.as_deref().unwrap_or("HTTP/1.1");
+
+        if !matches!(version, "HTTP/1.0" | "HTTP/1.1") {
+            abort!(
+                Span::call_site(),
+                "`raw_request!` cannot write {} requests", version;
+                help = "Only HTTP/1.0 and HTTP/1.1 requests are written as text, use `request!` for other versions"
+            );
         }
+        let mut wire = format!("{} {} {}\r\n", self.method, self.uri, version).into_bytes();
+
+        if self.signer.is_some() {
+            abort!(
+                Span::call_site(),
+                "The AWS signature is only known when the request is made";
+                help = "Add a `# @aws-date 20150830T123600Z` directive to sign the request when the macro runs"
+            );
+        }
+
+        for (name, value) in &self.headers {
+            if dynamic::is_runtime(value) {
+                abort!(
+                    Span::call_site(),
+                    "The `{}` header is only known when the request is made", name;
+                    help = "Use `request!` for requests with values that are made when the request is made"
+                );
+            }
+
+            let value = auth::header_value(name, value);
+            wire.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+
+        wire.extend_from_slice(b"\r\n");
+        self.body.to_wire(&mut wire);
+
+        wire
     }
 }
 
 impl ToTokens for Request {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let method = &self.method;
-        let uri = &self.uri;
+        let uri = dynamic::value_tokens(&self.uri);
         let version = get_version(self.version.as_ref());
         let headers = get_headers(self.headers.iter());
-
-        // Safe to unwrap since the TokenStream already makes sure it is a valid UTF-8 string
-        let body = String::from_utf8(self.body.clone()).unwrap();
+        let signature = self.signer.as_ref().map(Signer::header_tokens);
+        let body = &self.body;
 
         let builder = quote! {
             http::Request::builder()
@@ -52,10 +187,17 @@ impl ToTokens for Request {
                 .uri(#uri)
                 #version
                 #(#headers)*
-                .body(#body.to_string())
+                #signature
+                .body(#body)
         };
 
-        builder.to_tokens(tokens);
+        let tracking = get_tracking(&self.files, &self.env);
+        let signing = self.signer.as_ref().map(Signer::prelude_tokens);
+
+        match (tracking, signing) {
+            (None, None) => builder.to_tokens(tokens),
+            (tracking, signing) => quote! { { #tracking #signing #builder } }.to_tokens(tokens),
+        }
     }
 }
 
@@ -76,12 +218,32 @@ Host: localhost:8000
             uri: "/reminder".to_string(),
             version: None,
             headers: Vec::from([("Host".to_string(), "localhost:8000".to_string())]),
-            body: "{ \"note\": \"Buy milk\" }".as_bytes().to_vec(),
+            body: Body {
+                data: "{ \"note\": \"Buy milk\" }".as_bytes().to_vec(),
+                ..Default::default()
+            },
+            env: openapi::tracked_env(),
+            ..Default::default()
         };
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn content_length() {
+        let actual = Request::new(
+            r#"# @content-length
+POST /reminder
+
+{ "note": "Buy milk" }"#,
+        );
+
+        assert_eq!(
+            actual.headers,
+            Vec::from([("Content-Length".to_string(), "22".to_string())])
+        );
+    }
+
     #[test]
     fn output() {
         let input = Request {
@@ -89,7 +251,11 @@ Host: localhost:8000
             uri: "/health".to_string(),
             version: Some("HTTP/2.0".to_string()),
             headers: Vec::from([("Host".to_string(), "localhost:8000".to_string())]),
-            body: "{ \"note\": \"Buy milk\" }".as_bytes().to_vec(),
+            body: Body {
+                data: "{ \"note\": \"Buy milk\" }".as_bytes().to_vec(),
+                ..Default::default()
+            },
+            ..Default::default()
         };
         let expected = quote! {
             http::Request::builder()
@@ -102,4 +268,23 @@ Host: localhost:8000
 
         assert_eq!(input.to_token_stream().to_string(), expected.to_string());
     }
+
+    #[test]
+    fn wire() {
+        let input = Request::new(
+            r#"POST /reminder
+Host: localhost:8000
+Transfer-Encoding: chunked
+
+--- chunk ---
+Buy
+--- chunk ---
+ milk"#,
+        );
+
+        assert_eq!(
+            input.to_wire(),
+            b"POST /reminder HTTP/1.1\r\nHost: localhost:8000\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nBuy\r\n5\r\n milk\r\n0\r\n\r\n".to_vec()
+        );
+    }
 }
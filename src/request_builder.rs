@@ -3,8 +3,9 @@ use proc_macro_error::abort;
 use quote::{quote, ToTokens};
 
 use crate::{
+    aws, dynamic, environment, graphql, headers, openapi,
     parser::Parser,
-    token_helpers::{get_headers, get_version},
+    token_helpers::{get_headers, get_tracking, get_version},
 };
 
 /// Represents a request builder (which does not have a body).
@@ -14,17 +15,22 @@ pub struct RequestBuilder {
     uri: String,
     version: Option<String>,
     headers: Vec<(String, String)>,
+    files: Vec<String>,
+    env: Vec<String>,
 }
 
 impl RequestBuilder {
     pub fn new(input: &str) -> Self {
         let buf = input.as_bytes();
         let Parser {
+            directives,
+            variables: file_variables,
             method,
-            uri,
+            mut uri,
             version,
-            headers,
+            mut headers,
             body,
+            ..
         } = Parser::new(buf);
 
         if !body.is_empty() {
@@ -34,11 +40,44 @@ impl RequestBuilder {
             );
         }
 
+        headers::check_duplicates(&directives, &headers);
+
+        let mut files = Vec::new();
+        let mut env = Vec::new();
+        let environment = environment::load(&directives, input, file_variables);
+        environment
+            .variables
+            .substitute_request(&mut uri, &mut headers);
+        dynamic::resolve(
+            &mut uri,
+            &mut headers,
+            None,
+            &directives,
+            &mut files,
+            &mut env,
+        );
+        environment.track(&mut files, &mut env);
+        aws::reject(&directives, &headers);
+        graphql::remove_marker(&mut headers);
+
+        if let Some(document) = openapi::get_document(&directives, &mut env) {
+            let check = openapi::Check {
+                method: &method,
+                uri: &uri,
+                headers: &headers,
+                body: None,
+            };
+
+            files.push(openapi::validate(&document, check));
+        }
+
         Self {
             method,
             uri,
             version,
             headers,
+            files,
+            env,
         }
     }
 }
@@ -46,7 +85,7 @@ impl RequestBuilder {
 impl ToTokens for RequestBuilder {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let method = &self.method;
-        let uri = &self.uri;
+        let uri = dynamic::value_tokens(&self.uri);
         let version = get_version(self.version.as_ref());
         let headers = get_headers(self.headers.iter());
 
@@ -58,7 +97,10 @@ impl ToTokens for RequestBuilder {
                 #(#headers)*
         };
 
-        builder.to_tokens(tokens);
+        match get_tracking(&self.files, &self.env) {
+            Some(tracking) => quote! { { #tracking #builder } }.to_tokens(tokens),
+            None => builder.to_tokens(tokens),
+        }
     }
 }
 
@@ -72,6 +114,7 @@ mod tests {
         let expected = RequestBuilder {
             method: "GET".to_string(),
             uri: "/health".to_string(),
+            env: openapi::tracked_env(),
             ..Default::default()
         };
 
@@ -85,6 +128,7 @@ mod tests {
             method: "GET".to_string(),
             uri: "/health".to_string(),
             version: Some("HTTP/1.1".to_string()),
+            env: openapi::tracked_env(),
             ..Default::default()
         };
 
@@ -106,6 +150,8 @@ Accept: application/json"#,
                 ("Host".to_string(), "localhost:8000".to_string()),
                 ("Accept".to_string(), "application/json".to_string()),
             ]),
+            env: openapi::tracked_env(),
+            ..Default::default()
         };
 
         assert_eq!(actual, expected);
@@ -155,6 +201,7 @@ Accept: application/json"#,
                 ("Host".to_string(), "localhost:8000".to_string()),
                 ("Accept".to_string(), "application/json".to_string()),
             ]),
+            ..Default::default()
         };
         let expected = quote! {
             http::Request::builder()
@@ -0,0 +1,82 @@
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+use crate::files;
+
+/// Validates a JSON body against the JSON Schema in the file.
+/// Returns the full path of the schema file.
+pub fn validate(text: &str, path: &str) -> String {
+    // Without the feature nothing can be checked, so the file is not read either
+    if cfg!(not(feature = "json-schema")) {
+        missing_feature();
+    }
+
+    let (full_path, schema) = files::read(path);
+
+    let Ok(instance) = serde_json::from_str::<serde_json::Value>(text) else {
+        abort!(
+            Span::call_site(),
+            "The body is not valid JSON, so it cannot be validated against `{}`", path;
+            help = "Remove the `# @schema` directive to send invalid JSON"
+        );
+    };
+
+    let schema = match serde_json::from_str::<serde_json::Value>(&schema) {
+        Ok(schema) => schema,
+        Err(error) => abort!(Span::call_site(), "`{}` is not valid JSON: {}", path, error),
+    };
+
+    let errors = check(&schema, &instance, path);
+
+    if !errors.is_empty() {
+        abort!(
+            Span::call_site(),
+            "The body does not match the schema in `{}`", path;
+            note = "{}", errors.join("\n")
+        );
+    }
+
+    full_path
+}
+
+/// Returns all the places where the instance does not match the schema.
+#[cfg(feature = "json-schema")]
+pub fn check(schema: &serde_json::Value, instance: &serde_json::Value, path: &str) -> Vec<String> {
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(error) => abort!(
+            Span::call_site(),
+            "`{}` is not a valid JSON Schema: {}",
+            path,
+            error
+        ),
+    };
+
+    validator
+        .iter_errors(instance)
+        .map(|error| {
+            let location = error.instance_path().to_string();
+            let location = if location.is_empty() { "/" } else { &location };
+
+            format!("at `{location}`: {error}")
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "json-schema"))]
+pub fn check(
+    _schema: &serde_json::Value,
+    _instance: &serde_json::Value,
+    _path: &str,
+) -> Vec<String> {
+    missing_feature();
+}
+
+/// Aborts since schemas can only be checked with the `json-schema` feature.
+fn missing_feature() -> ! {
+    abort!(
+        Span::call_site(),
+        "`# @schema` needs the `json-schema` feature";
+        help = "Add `features = [\"json-schema\"]` to the `http-macros` dependency"
+    );
+}
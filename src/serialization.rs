@@ -0,0 +1,113 @@
+use crate::{headers, json};
+
+/// The binary formats a body written as JSON can be converted to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    MessagePack,
+    Cbor,
+}
+
+/// Gets the binary format named by the `Content-Type` header.
+pub fn get_format(headers: &[(String, String)]) -> Option<Format> {
+    match headers::media_type(headers)?.as_str() {
+        "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+            Some(Format::MessagePack)
+        }
+        "application/cbor" => Some(Format::Cbor),
+        media_type if media_type.ends_with("+cbor") => Some(Format::Cbor),
+        _ => None,
+    }
+}
+
+/// Converts the body from JSON to the binary format.
+pub fn encode(text: &str, format: Format) -> Vec<u8> {
+    // A request without a body is fine
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+
+    let value = json::parse(text);
+
+    match format {
+        Format::MessagePack => msgpack(&value),
+        Format::Cbor => cbor(&value),
+    }
+}
+
+#[cfg(feature = "msgpack")]
+fn msgpack(value: &serde_json::Value) -> Vec<u8> {
+    // Maps are written with their keys, like in the JSON, instead of as arrays of values
+    rmp_serde::to_vec_named(value).unwrap()
+}
+
+#[cfg(not(feature = "msgpack"))]
+fn msgpack(_value: &serde_json::Value) -> Vec<u8> {
+    missing_feature("MessagePack", "msgpack")
+}
+
+#[cfg(feature = "cbor")]
+fn cbor(value: &serde_json::Value) -> Vec<u8> {
+    let mut data = Vec::new();
+    ciborium::into_writer(value, &mut data).unwrap();
+
+    data
+}
+
+#[cfg(not(feature = "cbor"))]
+fn cbor(_value: &serde_json::Value) -> Vec<u8> {
+    missing_feature("CBOR", "cbor")
+}
+
+/// Aborts because the feature for a binary format is not turned on.
+#[cfg(not(all(feature = "msgpack", feature = "cbor")))]
+fn missing_feature(format: &str, feature: &str) -> ! {
+    use proc_macro::Span;
+    use proc_macro_error::abort;
+
+    abort!(
+        Span::call_site(),
+        "Converting the body to {} needs the `{}` feature", format, feature;
+        help = "Add `features = [\"{}\"]` to the `http-macros` dependency", feature
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content_type(value: &str) -> Vec<(String, String)> {
+        Vec::from([("Content-Type".to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            get_format(&content_type("application/msgpack")),
+            Some(Format::MessagePack)
+        );
+        assert_eq!(
+            get_format(&content_type("application/senml+cbor")),
+            Some(Format::Cbor)
+        );
+        assert_eq!(get_format(&content_type("application/json")), None);
+        assert_eq!(get_format(&[]), None);
+    }
+
+    #[test]
+    #[cfg(feature = "msgpack")]
+    fn msgpack() {
+        assert_eq!(
+            encode(r#"{ "a": [1, -1, true, null] }"#, Format::MessagePack),
+            b"\x81\xa1a\x94\x01\xff\xc3\xc0"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "cbor")]
+    fn cbor() {
+        assert_eq!(
+            encode(r#"{ "a": [1, -1, true, null] }"#, Format::Cbor),
+            b"\xa1\x61a\x84\x01\x20\xf5\xf6"
+        );
+    }
+}
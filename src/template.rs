@@ -0,0 +1,322 @@
+use proc_macro::{Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Creates the `macro_rules!` for a `request_template!(NAME, "...")`.
+/// The template macro calls the request macro back with the template text, so `request!(extends NAME, "...")` can merge the two.
+pub fn define(input: TokenStream) -> proc_macro2::TokenStream {
+    let mut tokens = input.into_iter();
+
+    let (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(comma))) =
+        (tokens.next(), tokens.next())
+    else {
+        abort!(
+            Span::call_site(),
+            "Missing template name";
+            help = "Try `request_template!(BASE, \"Host: example.com\")`"
+        );
+    };
+
+    if comma.as_char() != ',' {
+        abort!(
+            comma.span(),
+            "Expected a `,` after the template name";
+            help = "Try `request_template!({}, \"Host: example.com\")`", name
+        );
+    }
+
+    let template = crate::get_request(tokens.collect());
+    check(&template);
+
+    let name = proc_macro2::Ident::new(&name.to_string(), name.span().into());
+    let template = raw_string(&template);
+    let krate = proc_macro2::Ident::new(&crate_name(), proc_macro2::Span::call_site());
+
+    quote! {
+        #[allow(unused_macros)]
+        macro_rules! #name {
+            ($macro:ident, $($request:tt)*) => {
+                ::#krate::$macro!(@template #template, $($request)*)
+            };
+        }
+    }
+}
+
+/// Gets the name this crate has in the crate which uses the macro, which is different when the dependency is renamed with `package = "http-macros"`.
+fn crate_name() -> String {
+    let manifest = crate::files::resolve("Cargo.toml");
+    let content = std::fs::read_to_string(manifest).unwrap_or_default();
+
+    renamed(&content, env!("CARGO_PKG_NAME"))
+        .unwrap_or(env!("CARGO_PKG_NAME"))
+        .replace('-', "_")
+}
+
+/// Finds the name of a dependency on `package` in a `Cargo.toml`, when it is renamed.
+/// It can be renamed with `name = { package = "..." }` in a dependencies table, or in a `[dependencies.name]` table.
+fn renamed<'a>(manifest: &'a str, package: &str) -> Option<&'a str> {
+    let is_package = |text: &str| {
+        text.split_once("package")
+            .and_then(|(_, rest)| rest.trim_start().strip_prefix('='))
+            .is_some_and(|rest| rest.trim_start().starts_with(&format!("\"{package}\"")))
+    };
+    let mut section = "";
+
+    for line in manifest.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[') {
+            section = header.trim_end_matches(']').trim();
+            continue;
+        }
+
+        // A `[dependencies.name]` table
+        if let Some((_, name)) = section.rsplit_once("dependencies.") {
+            if is_package(line) {
+                return Some(name.trim_matches('"'));
+            }
+            continue;
+        }
+
+        if section.ends_with("dependencies") && is_package(line) {
+            return line
+                .split_once('=')
+                .map(|(name, _)| name.trim().trim_matches('"'));
+        }
+    }
+
+    None
+}
+
+/// Turns `extends NAME, "..."` into a call of the template macro, which calls `macro_name` back with the template.
+pub fn extends(input: &TokenStream, macro_name: &str) -> Option<proc_macro2::TokenStream> {
+    let mut tokens = input.clone().into_iter();
+
+    let Some(TokenTree::Ident(extends)) = tokens.next() else {
+        return None;
+    };
+
+    if extends.to_string() != "extends" {
+        return None;
+    }
+
+    let (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(comma))) =
+        (tokens.next(), tokens.next())
+    else {
+        abort!(
+            extends.span(),
+            "Missing template name";
+            help = "Try `{}!(extends BASE, \"GET /hello\")`", macro_name
+        );
+    };
+
+    if comma.as_char() != ',' {
+        abort!(
+            comma.span(),
+            "Expected a `,` after the template name";
+            help = "Try `{}!(extends {}, \"GET /hello\")`", macro_name, name
+        );
+    }
+
+    let name = proc_macro2::Ident::new(&name.to_string(), name.span().into());
+    let macro_name = proc_macro2::Ident::new(macro_name, proc_macro2::Span::call_site());
+    let request = proc_macro2::TokenStream::from(tokens.collect::<TokenStream>());
+
+    Some(quote! {
+        #name!(#macro_name, #request)
+    })
+}
+
+/// Writes the text as a raw string literal, with more `#` than any `"#` in the text so that the literal is not closed early.
+fn raw_string(text: &str) -> proc_macro2::TokenStream {
+    let longest = text
+        .split('"')
+        .skip(1)
+        .map(|after| after.len() - after.trim_start_matches('#').len())
+        .max()
+        .unwrap_or_default();
+    let hashes = "#".repeat(longest + 1);
+
+    format!("r{hashes}\"{text}\"{hashes}").parse().unwrap()
+}
+
+/// Splits `@template "..." , rest` (as written by the template macro) into the template and the rest.
+pub fn split_template(input: &TokenStream) -> Option<(TokenStream, TokenStream)> {
+    let mut tokens = input.clone().into_iter();
+
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Punct(at)), Some(TokenTree::Ident(template)))
+            if at.as_char() == '@' && template.to_string() == "template" => {}
+        _ => return None,
+    }
+
+    let template = tokens.next().into_iter().collect();
+    tokens.next();
+
+    Some((template, tokens.collect()))
+}
+
+/// Stops when a template has something other than comments, file variables, headers and a body.
+fn check(template: &str) {
+    let (_, headers, _) = split(template);
+
+    if let Some(line) = headers.iter().find(|line| !line.contains(':')) {
+        abort!(
+            Span::call_site(),
+            "Invalid template header `{}`", line;
+            help = "A template only has headers and a body, the request line goes in `request!(extends ...)`"
+        );
+    }
+}
+
+/// Merges a request with the template it extends.
+/// The comments and file variables of both are kept, and the body of the request replaces the body of the template.
+/// A header of the request replaces the headers with the same name in the template (like `=Name: value`), `+Name: value` adds another value, and `-Name` removes the header.
+pub fn merge(template: &str, request: &str) -> String {
+    let (template_preamble, template_headers, template_body) = split(template);
+    let (request_preamble, request_lines, request_body) = split(request);
+
+    let Some((request_line, request_headers)) = request_lines.split_first() else {
+        abort!(
+            Span::call_site(),
+            "Missing request line";
+            help = "Add a request line after the template, like `GET /hello`"
+        );
+    };
+
+    let mut headers: Vec<String> = template_headers
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+    for line in request_headers {
+        if let Some(name) = line.strip_prefix('-') {
+            let name = name.trim().trim_end_matches(':');
+            headers.retain(|header| !has_name(header, name));
+        } else if let Some(header) = line.strip_prefix('+') {
+            headers.push(header.to_string());
+        } else {
+            let line = line.strip_prefix('=').unwrap_or(line);
+            let name = line.split(':').next().unwrap_or_default();
+
+            let mut replaced = false;
+            headers = headers
+                .into_iter()
+                .filter_map(|header| match has_name(&header, name) {
+                    false => Some(header),
+                    true if replaced => None,
+                    true => {
+                        replaced = true;
+                        Some(line.to_string())
+                    }
+                })
+                .collect();
+
+            if !replaced {
+                headers.push(line.to_string());
+            }
+        }
+    }
+
+    let body = request_body.or(template_body);
+
+    let mut merged = template_preamble;
+    merged.extend(request_preamble);
+    merged.push(request_line);
+    merged.extend(headers.iter().map(String::as_str));
+
+    let mut merged = merged.join("\n");
+
+    if let Some(body) = body {
+        merged.push_str("\n\n");
+        merged.push_str(body);
+    }
+
+    merged
+}
+
+/// Returns whether a header line has the (case-insensitive) name.
+fn has_name(line: &str, name: &str) -> bool {
+    line.split(':')
+        .next()
+        .is_some_and(|header| header.trim().eq_ignore_ascii_case(name.trim()))
+}
+
+/// Splits a request (or template) into its comment and file variable lines, the request line and headers, and the body.
+fn split(text: &str) -> (Vec<&str>, Vec<&str>, Option<&str>) {
+    let mut preamble = Vec::new();
+    let mut rest = text;
+
+    while let Some('#' | '@' | '\n') = rest.chars().next() {
+        let (line, remaining) = rest.split_once('\n').unwrap_or((rest, ""));
+        preamble.push(line);
+        rest = remaining;
+    }
+
+    let (lines, body) = match rest.split_once("\n\n") {
+        Some((lines, body)) => (lines, Some(body)),
+        None => (rest, None),
+    };
+
+    (
+        preamble,
+        lines.lines().filter(|line| !line.is_empty()).collect(),
+        body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_headers() {
+        let template = "# @content-length\nHost: example.com\nAccept: text/plain\nAuthorization: Bearer x\nAccept: text/html";
+        let request =
+            "POST /users\nAccept: application/json\n+Authorization: Basic a b\n-Host\n\n{}";
+
+        assert_eq!(
+            merge(template, request),
+            "# @content-length\nPOST /users\nAccept: application/json\nAuthorization: Bearer x\nAuthorization: Basic a b\n\n{}"
+        );
+    }
+
+    #[test]
+    fn merge_prefixed_headers() {
+        let template =
+            "Host: example.com\nAccept: text/plain\nAuthorization: Bearer x\nAccept: text/html";
+        let request = "POST /users\n=Accept: application/json\n+Accept: text/csv\n-Host";
+
+        assert_eq!(
+            merge(template, request),
+            "POST /users\nAccept: application/json\nAuthorization: Bearer x\nAccept: text/csv"
+        );
+
+        assert_eq!(
+            merge(template, "GET /users\nAuthorization: Bearer y"),
+            "GET /users\nHost: example.com\nAccept: text/plain\nAuthorization: Bearer y\nAccept: text/html"
+        );
+    }
+
+    #[test]
+    fn renamed_dependency() {
+        let manifest = "[package]\nname = \"app\"\n\n[dependencies]\nhttp = \"1\"\nmacros = { package = \"http-macros\", version = \"0.1\" }\n";
+        assert_eq!(renamed(manifest, "http-macros"), Some("macros"));
+
+        let manifest = "[dev-dependencies.macros]\nversion = \"0.1\"\npackage = \"http-macros\"\n";
+        assert_eq!(renamed(manifest, "http-macros"), Some("macros"));
+
+        let manifest = "[dependencies]\nhttp-macros = \"0.1\"\n";
+        assert_eq!(renamed(manifest, "http-macros"), None);
+    }
+
+    #[test]
+    fn merge_body() {
+        assert_eq!(
+            merge("Host: example.com\n\nHello", "@name = x\nPOST /hello"),
+            "@name = x\nPOST /hello\nHost: example.com\n\nHello"
+        );
+        assert_eq!(
+            merge("Host: example.com\n\nHello", "POST /hello\n\nWorld"),
+            "POST /hello\nHost: example.com\n\nWorld"
+        );
+    }
+}
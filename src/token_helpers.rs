@@ -2,6 +2,8 @@ use proc_macro::Span;
 use proc_macro_error::abort;
 use quote::{format_ident, quote};
 
+use crate::auth;
+
 /// Get the correct [http::Version] from a string.
 pub fn get_version(version: Option<&String>) -> Option<proc_macro2::TokenStream> {
     version
@@ -26,8 +28,37 @@ pub fn get_headers<'a>(
     headers: impl Iterator<Item = &'a (String, String)> + 'a,
 ) -> impl Iterator<Item = proc_macro2::TokenStream> + 'a {
     headers.map(|(name, value)| {
+        let value = auth::header_tokens(name, value);
+
         quote! {
             .header(#name, #value)
         }
     })
 }
+
+/// Get the items which make the compiler rebuild the macro when a file or environment variable it read changes.
+pub fn get_tracking(files: &[String], env: &[String]) -> Option<proc_macro2::TokenStream> {
+    if files.is_empty() && env.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        #(const _: &[u8] = include_bytes!(#files);)*
+        #(const _: Option<&str> = option_env!(#env);)*
+    })
+}
+
+/// Defines a function, and a function returning its tokens so the same code can run when the request is made.
+macro_rules! with_tokens {
+    ($tokens:ident, $(#[$attr:meta])* fn $name:ident $($rest:tt)*) => {
+        $(#[$attr])*
+        fn $name $($rest)*
+
+        /// The tokens of the function with the same body, for the code generated by the macro.
+        fn $tokens() -> proc_macro2::TokenStream {
+            ::quote::quote! { fn $name $($rest)* }
+        }
+    };
+}
+
+pub(crate) use with_tokens;
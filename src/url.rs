@@ -0,0 +1,24 @@
+/// Percent-encodes everything in the value except the unreserved characters of RFC 3986.
+pub fn encode_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn component() {
+        assert_eq!(encode_component("John Doe"), "John%20Doe");
+        assert_eq!(encode_component("a,b&c=d"), "a%2Cb%26c%3Dd");
+        assert_eq!(encode_component("café~"), "caf%C3%A9~");
+    }
+}
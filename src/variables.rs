@@ -0,0 +1,228 @@
+use std::cell::Cell;
+
+use proc_macro::Span;
+use proc_macro_error::abort;
+
+/// The variables that can be used as `{{name}}`.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Variables {
+    values: Vec<(String, String)>,
+
+    /// Whether a variable was substituted, so that the files the variables came from need to be tracked.
+    used: Cell<bool>,
+
+    /// Whether unknown variables are kept as they are, with `# @allow undefined-variable`, like the `{{name}}` of a Mustache template.
+    keep_unknown: bool,
+}
+
+impl Variables {
+    pub fn new(values: Vec<(String, String)>, keep_unknown: bool) -> Self {
+        Self {
+            values,
+            used: Cell::new(false),
+            keep_unknown,
+        }
+    }
+
+    /// Returns whether any variable was substituted.
+    pub fn was_used(&self) -> bool {
+        self.used.get()
+    }
+
+    /// Returns the value of a variable.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replaces the `{{name}}` variables in the URI and header values.
+    pub fn substitute_request(&self, uri: &mut String, headers: &mut [(String, String)]) {
+        *uri = self.substitute(uri);
+
+        for (_, value) in headers {
+            *value = self.substitute(value);
+        }
+    }
+
+    /// Adds the file variables declared with `@name = value`, which take precedence over the other variables.
+    /// A file variable can refer to other file variables and to the other variables.
+    pub fn declare(&mut self, declared: Vec<(String, String)>) {
+        let mut resolved = Vec::new();
+
+        for (name, _) in &declared {
+            self.resolve_declared(name, &declared, &mut resolved, &mut Vec::new());
+        }
+
+        resolved.append(&mut self.values);
+        self.values = resolved;
+    }
+
+    /// Works out the value of a file variable, after working out the file variables it refers to.
+    fn resolve_declared(
+        &self,
+        name: &str,
+        declared: &[(String, String)],
+        resolved: &mut Vec<(String, String)>,
+        stack: &mut Vec<String>,
+    ) -> String {
+        if let Some((_, value)) = resolved.iter().find(|(n, _)| n == name) {
+            return value.clone();
+        }
+
+        if stack.iter().any(|n| n == name) {
+            stack.push(name.to_string());
+
+            abort!(
+                Span::call_site(),
+                "The file variable `{}` refers to itself", name;
+                help = "Break the cycle: {}", stack.join(" -> ")
+            );
+        }
+
+        let Some((_, value)) = declared.iter().rev().find(|(n, _)| n == name) else {
+            unreachable!("only declared variables are resolved");
+        };
+
+        stack.push(name.to_string());
+        let value = replace(value, |reference| {
+            if declared.iter().any(|(n, _)| n == reference) {
+                Some(self.resolve_declared(reference, declared, resolved, stack))
+            } else {
+                self.used.set(true);
+                self.lookup(reference)
+            }
+        });
+        stack.pop();
+
+        resolved.push((name.to_string(), value.clone()));
+
+        value
+    }
+
+    /// Replaces the `{{name}}` variables in the text with their values.
+    /// Dynamic variables, like `{{$guid}}`, are kept for when the dynamic variables are worked out.
+    pub fn substitute(&self, text: &str) -> String {
+        replace(text, |name| {
+            let value = self.lookup(name);
+            self.used.set(true);
+
+            value
+        })
+    }
+
+    /// Returns the value of a variable, or stops with an error when it does not exist.
+    /// With `# @allow undefined-variable` there is no value for an unknown variable, so that it is kept as it is.
+    fn lookup(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.get(name) {
+            return Some(value.to_string());
+        }
+
+        if self.keep_unknown {
+            return None;
+        }
+
+        let known: Vec<_> = self.values.iter().map(|(name, _)| name.as_str()).collect();
+
+        abort!(
+            Span::call_site(),
+            "Unknown variable `{}`", name;
+            help = "The known variables are: {}. Add `# @allow undefined-variable` to send `{{{{{}}}}}` as it is",
+                if known.is_empty() { "none".to_string() } else { known.join(", ") },
+                name
+        );
+    }
+}
+
+/// Replaces the `{{name}}` variables in the text with the value given by `value`.
+/// Dynamic variables, like `{{$guid}}`, and variables without a value are kept as they are.
+fn replace(text: &str, mut value: impl FnMut(&str) -> Option<String>) -> String {
+    let mut substituted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some((start, end)) = rest
+        .find("{{")
+        .and_then(|start| Some((start, start + rest[start..].find("}}")?)))
+    {
+        let name = rest[start + 2..end].trim();
+
+        if name.starts_with('$') {
+            substituted.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        match value(name) {
+            Some(value) => {
+                substituted.push_str(&rest[..start]);
+                substituted.push_str(&value);
+            }
+            None => substituted.push_str(&rest[..end + 2]),
+        }
+
+        rest = &rest[end + 2..];
+    }
+
+    substituted.push_str(rest);
+
+    substituted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitute() {
+        let variables = Variables::new(
+            Vec::from([
+                ("name".to_string(), "John Doe".to_string()),
+                ("age".to_string(), "42".to_string()),
+            ]),
+            false,
+        );
+
+        assert_eq!(
+            variables.substitute(r#"{ "name": "{{name}}", "age": {{ age }} }"#),
+            r#"{ "name": "John Doe", "age": 42 }"#
+        );
+        assert_eq!(variables.substitute("{{name"), "{{name");
+        assert_eq!(variables.substitute("no variables"), "no variables");
+        assert_eq!(
+            variables.substitute("{{$jwt HS256 key=a.key {\"sub\":\"{{name}}\"}}}"),
+            "{{$jwt HS256 key=a.key {\"sub\":\"John Doe\"}}}"
+        );
+    }
+
+    #[test]
+    fn declare() {
+        let mut variables = Variables::new(
+            Vec::from([
+                ("host".to_string(), "example.com".to_string()),
+                ("version".to_string(), "v1".to_string()),
+            ]),
+            false,
+        );
+        variables.declare(Vec::from([
+            ("url".to_string(), "https://{{host}}/{{ api }}".to_string()),
+            ("api".to_string(), "api/{{version}}".to_string()),
+            ("host".to_string(), "localhost".to_string()),
+            ("id".to_string(), "{{$guid}}".to_string()),
+        ]));
+
+        assert_eq!(variables.get("url"), Some("https://localhost/api/v1"));
+        assert_eq!(variables.get("host"), Some("localhost"));
+        assert_eq!(variables.get("id"), Some("{{$guid}}"));
+    }
+
+    #[test]
+    fn keep_unknown() {
+        let variables = Variables::new(Vec::from([("name".to_string(), "Ann".to_string())]), true);
+
+        assert_eq!(
+            variables.substitute("{{#each items}}{{name}} {{ title }}{{/each}}"),
+            "{{#each items}}Ann {{ title }}{{/each}}"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "aws")]
+
+use hmac::{Hmac, Mac};
+use http_macros::request;
+use sha2::{Digest, Sha256};
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[test]
+fn test_aws_fixed_date() {
+    let request = request!(
+        "# @aws-date 20150830T123600Z
+         GET https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08
+         Content-Type: application/x-www-form-urlencoded; charset=utf-8
+         Authorization: AWS AKIDEXAMPLE wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"
+    );
+    assert_eq!(request.headers().get("Host").unwrap(), "iam.amazonaws.com");
+    assert_eq!(
+        request.headers().get("X-Amz-Date").unwrap(),
+        "20150830T123600Z"
+    );
+    assert_eq!(
+        request.headers().get("Authorization").unwrap(),
+        "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+    );
+}
+
+#[test]
+fn test_aws_current_date() {
+    let request = request!(
+        "GET /health
+         Host: localhost:4566
+         Authorization: AWS AKIDEXAMPLE secret region:eu-west-1 service:execute-api"
+    );
+
+    let amz_date = request
+        .headers()
+        .get("X-Amz-Date")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert_eq!(amz_date.len(), 16);
+
+    let date = &amz_date[..8];
+    let canonical_request = format!(
+        "GET\n/health\n\nhost:localhost:4566\nx-amz-date:{amz_date}\n\nhost;x-amz-date\n{}",
+        hex(&Sha256::digest(b""))
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{date}/eu-west-1/execute-api/aws4_request\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = ["eu-west-1", "execute-api", "aws4_request"]
+        .iter()
+        .fold(hmac(b"AWS4secret", date), |key, data| hmac(&key, data));
+
+    assert_eq!(
+        request.headers().get("Authorization").unwrap(),
+        &format!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/{date}/eu-west-1/execute-api/aws4_request, SignedHeaders=host;x-amz-date, Signature={}",
+            hex(&hmac(&key, &string_to_sign))
+        )
+    );
+}
@@ -0,0 +1,96 @@
+#![cfg(all(
+    feature = "gzip",
+    feature = "deflate",
+    feature = "br",
+    feature = "zstd"
+))]
+
+use std::io::Read;
+
+use http_macros::request;
+
+#[test]
+fn test_gzip() {
+    let request = request!(
+        r#"POST /reminder
+           Content-Encoding: gzip
+
+           { "note": "Buy milk" }"#
+    );
+    let mut body = String::new();
+    flate2::read::GzDecoder::new(request.body().as_slice())
+        .read_to_string(&mut body)
+        .unwrap();
+
+    assert_eq!(body, r#"{ "note": "Buy milk" }"#);
+}
+
+#[test]
+fn test_deflate() {
+    let request = request!(
+        r#"POST /reminder
+           Content-Encoding: deflate
+
+           { "note": "Buy milk" }"#
+    );
+    let mut body = String::new();
+    flate2::read::ZlibDecoder::new(request.body().as_slice())
+        .read_to_string(&mut body)
+        .unwrap();
+
+    assert_eq!(body, r#"{ "note": "Buy milk" }"#);
+}
+
+#[test]
+fn test_br() {
+    let request = request!(
+        r#"POST /reminder
+           Content-Encoding: br
+
+           { "note": "Buy milk" }"#
+    );
+    let mut body = String::new();
+    brotli::Decompressor::new(request.body().as_slice(), 4096)
+        .read_to_string(&mut body)
+        .unwrap();
+
+    assert_eq!(body, r#"{ "note": "Buy milk" }"#);
+}
+
+#[test]
+fn test_zstd() {
+    let request = request!(
+        r#"POST /reminder
+           Content-Encoding: zstd
+
+           { "note": "Buy milk" }"#
+    );
+    let body = zstd::decode_all(request.body().as_slice()).unwrap();
+
+    assert_eq!(body, br#"{ "note": "Buy milk" }"#);
+}
+
+#[test]
+fn test_multiple_codings() {
+    let request = request!(
+        r#"# @content-length
+           POST /reminder
+           Content-Encoding: deflate, gzip
+
+           { "note": "Buy milk" }"#
+    );
+    let mut deflated = Vec::new();
+    flate2::read::GzDecoder::new(request.body().as_slice())
+        .read_to_end(&mut deflated)
+        .unwrap();
+    let mut body = String::new();
+    flate2::read::ZlibDecoder::new(deflated.as_slice())
+        .read_to_string(&mut body)
+        .unwrap();
+
+    assert_eq!(body, r#"{ "note": "Buy milk" }"#);
+    assert_eq!(
+        request.headers().get("Content-Length").unwrap(),
+        &request.body().len().to_string()
+    );
+}
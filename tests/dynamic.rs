@@ -0,0 +1,123 @@
+use http_macros::request;
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[test]
+fn test_guid() {
+    let request = request!(
+        "POST /notes/{{$guid}}
+         X-Request-Id: {{$guid}}"
+    );
+    let id = request
+        .headers()
+        .get("X-Request-Id")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert_eq!(id.len(), 36);
+    assert_eq!(&id[14..15], "4");
+    assert_ne!(request.uri().path(), format!("/notes/{id}"));
+}
+
+#[test]
+fn test_random_int() {
+    for _ in 0..20 {
+        let request = request!(
+            "GET /notes
+             X-Page: {{$randomInt 1 4}}"
+        );
+        let page: i64 = request.headers()["X-Page"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..4).contains(&page));
+    }
+}
+
+#[test]
+fn test_random_seed() {
+    fn make() -> http::Request<String> {
+        request!(
+            "# @random-seed 42
+             GET /notes/{{$guid}}
+             X-Page: {{$randomInt 1 1000000}}"
+        )
+    }
+
+    let (first, second) = (make(), make());
+    assert_eq!(first.uri(), second.uri());
+    assert_eq!(first.headers()["X-Page"], second.headers()["X-Page"]);
+}
+
+#[test]
+fn test_timestamp() {
+    let request = request!(
+        "GET /notes
+         X-Now: {{$timestamp}}
+         X-Earlier: {{$timestamp -3 h}}"
+    );
+    let header = |name: &str| -> i64 { request.headers()[name].to_str().unwrap().parse().unwrap() };
+    assert!((header("X-Now") - now()).abs() <= 5);
+    assert!((header("X-Earlier") - (now() - 3 * 60 * 60)).abs() <= 5);
+}
+
+#[test]
+fn test_datetime() {
+    let request = request!(
+        r#"GET /notes
+           X-Iso: {{$datetime iso8601}}
+           X-Rfc: {{$datetime rfc1123 1 d}}
+           X-Custom: {{$datetime "YYYY-MM-DD" -1 y}}
+           X-Local: {{$localDatetime iso8601}}"#
+    );
+    let header = |name: &str| request.headers()[name].to_str().unwrap().to_string();
+
+    let iso = chrono::DateTime::parse_from_rfc3339(&header("X-Iso")).unwrap();
+    assert!((iso.timestamp() - now()).abs() <= 5);
+
+    let rfc = chrono::DateTime::parse_from_rfc2822(&header("X-Rfc")).unwrap();
+    assert!((rfc.timestamp() - (now() + 24 * 60 * 60)).abs() <= 5);
+
+    let year_ago = chrono::Utc::now()
+        .checked_sub_months(chrono::Months::new(12))
+        .unwrap();
+    assert_eq!(header("X-Custom"), year_ago.format("%Y-%m-%d").to_string());
+
+    let local = chrono::DateTime::parse_from_rfc3339(&header("X-Local")).unwrap();
+    assert!((local.timestamp() - now()).abs() <= 5);
+}
+
+#[test]
+fn test_body() {
+    let request = request!(
+        "@note = {{$guid}}
+         POST /notes
+         Content-Type: text/plain
+
+         id={{$guid}} at={{$timestamp}} note={{note}}"
+    );
+    let body = request.body();
+    assert!(!body.contains("{{"));
+
+    let at: i64 = body.split(' ').nth(1).unwrap()["at=".len()..]
+        .parse()
+        .unwrap();
+    assert!((now() - at).abs() < 5);
+
+    let request = request!(
+        r#"# @random-seed 7
+           # @content-length
+           POST /notes
+           Content-Type: application/json
+
+           { "id": "{{$guid}}" }"#
+    );
+    assert_eq!(request.headers()["Content-Length"], "48");
+    assert!(!request.body().contains("{{"));
+}
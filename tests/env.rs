@@ -0,0 +1,70 @@
+use std::sync::Mutex;
+
+use http_macros::request;
+
+/// The tests which change or read environment variables when the request is made run one at a time.
+static ENV: Mutex<()> = Mutex::new(());
+
+#[test]
+fn test_process_env() {
+    let request = request!(
+        "GET /packages
+         X-Package: {{$processEnv CARGO_PKG_NAME}}"
+    );
+    assert_eq!(request.headers()["X-Package"], "http-macros");
+}
+
+#[test]
+fn test_process_env_runtime() {
+    let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+    std::env::set_var("HTTP_MACROS_TEST_TOKEN", "from-the-test");
+
+    let request = request!(
+        "# @resolve-env runtime
+         GET /notes
+         Authorization: Bearer {{$processEnv HTTP_MACROS_TEST_TOKEN}}"
+    );
+    assert_eq!(request.headers()["Authorization"], "Bearer from-the-test");
+}
+
+#[test]
+fn test_process_env_runtime_basic_authorization() {
+    let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+    std::env::set_var("HTTP_MACROS_TEST_USER", "alice");
+
+    let request = request!(
+        "# @resolve-env runtime
+         GET /notes
+         Authorization: Basic {{$processEnv HTTP_MACROS_TEST_USER}}:secret"
+    );
+    assert_eq!(request.headers()["Authorization"], "Basic YWxpY2U6c2VjcmV0");
+}
+
+#[test]
+#[should_panic(expected = "The environment variable `HTTP_MACROS_TEST_MISSING` is not set")]
+fn test_process_env_runtime_missing() {
+    let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+    request!(
+        "# @resolve-env runtime
+         GET /notes
+         X-Missing: {{$processEnv HTTP_MACROS_TEST_MISSING}}"
+    );
+}
+
+#[test]
+fn test_dotenv() {
+    let request = request!(
+        "GET {{$dotenv BASE_URL}}/notes
+         X-Api-Key: {{$dotenv API_KEY}}"
+    );
+    assert_eq!(request.uri(), "http://localhost:8000/notes");
+    assert_eq!(request.headers()["X-Api-Key"], "local-key");
+
+    let request = request!(
+        "# @resolve-env runtime
+         GET {{$dotenv BASE_URL}}/notes
+         X-Api-Key: {{$dotenv API_KEY}}"
+    );
+    assert_eq!(request.uri(), "http://localhost:8000/notes");
+    assert_eq!(request.headers()["X-Api-Key"], "local-key");
+}
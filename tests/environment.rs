@@ -0,0 +1,73 @@
+use http_macros::request;
+
+#[test]
+fn test_environment() {
+    let request = request!(
+        r#"# @environment local
+           POST {{baseUrl}}/notes
+           Authorization: Bearer {{token}}
+
+           { "version": "{{version}}" }"#
+    );
+    assert_eq!(request.uri(), "http://localhost:8000/notes");
+    assert_eq!(request.headers()["Authorization"], "Bearer local-token");
+    assert_eq!(request.body(), r#"{ "version": "v1" }"#);
+}
+
+#[test]
+fn test_environment_references() {
+    let request = request!(
+        "# @environment staging
+         GET {{baseUrl}}/notes
+         X-Token: {{token}}"
+    );
+    assert_eq!(request.uri(), "https://staging.example.com/v1/notes");
+    assert_eq!(request.headers()["X-Token"], "http-macros");
+}
+
+#[test]
+fn test_shared_variables() {
+    let request = request!(
+        "GET /{{version}}/notes
+         X-Request-Id: {{$guid}}"
+    );
+    assert_eq!(request.uri(), "/v1/notes");
+}
+
+#[test]
+fn test_file_variables() {
+    let request = request!(
+        r#"@host = localhost:8080
+           @url = http://{{host}}/{{version}}
+           # @environment local
+           @id = {{$guid}}
+           POST {{url}}/notes
+           Authorization: Bearer {{token}}
+           X-Request-Id: {{id}}
+
+           { "host": "{{host}}" }"#
+    );
+    assert_eq!(request.uri(), "http://localhost:8080/v1/notes");
+    assert_eq!(request.headers()["Authorization"], "Bearer local-token");
+    assert_eq!(request.headers()["X-Request-Id"].len(), 36);
+    assert_eq!(request.body(), r#"{ "host": "localhost:8080" }"#);
+
+    let request = request!(
+        "# @environment local
+         @baseUrl = https://example.com
+         GET {{baseUrl}}/notes"
+    );
+    assert_eq!(request.uri(), "https://example.com/notes");
+}
+
+#[test]
+fn test_undefined_variables_allowed() {
+    let request = request!(
+        "# @allow undefined-variable
+         POST /templates
+         Content-Type: text/plain
+
+         {{#each notes}}{{ title }} for {{version}}{{/each}}"
+    );
+    assert_eq!(request.body(), "{{#each notes}}{{ title }} for v1{{/each}}");
+}
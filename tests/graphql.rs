@@ -0,0 +1,47 @@
+#![cfg(feature = "graphql")]
+
+use http_macros::request;
+
+#[test]
+fn test_graphql_schema() {
+    let request = request!(
+        r#"# @graphql-schema tests/schemas/reminders.graphql
+           POST /graphql
+           Content-Type: application/json
+           X-REQUEST-TYPE: GraphQL
+
+           mutation ($note: String!) {
+             createReminder(note: $note) { id done }
+           }
+
+           { "note": "Buy milk" }"#
+    );
+    assert!(request.body().starts_with(r#"{"query":"mutation"#));
+
+    let request = request!(
+        "# @graphql-schema tests/schemas/reminders.graphql
+         POST /graphql
+         Content-Type: application/graphql
+
+         { reminders(done: false) { note } }"
+    );
+    assert_eq!(*request.body(), "{ reminders(done: false) { note } }");
+}
+
+#[test]
+fn test_invalid_graphql_allowed() {
+    let request = request!(
+        "# @allow invalid-graphql
+         POST /graphql
+         Content-Type: application/graphql
+
+         { reminders {"
+    );
+    assert_eq!(*request.body(), "{ reminders {");
+}
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/graphql/*.rs");
+}
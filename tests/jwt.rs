@@ -0,0 +1,59 @@
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use http_macros::request;
+use sha2::Sha256;
+
+/// Checks the signature of the token and returns its claims.
+fn verify(authorization: &http::HeaderValue) -> serde_json::Value {
+    let token = authorization
+        .to_str()
+        .unwrap()
+        .strip_prefix("Bearer ")
+        .unwrap();
+    let (message, signature) = token.rsplit_once('.').unwrap();
+
+    let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    let mut mac = Hmac::<Sha256>::new_from_slice(b"our-test-secret").unwrap();
+    mac.update(message.as_bytes());
+    mac.verify_slice(&engine.decode(signature).unwrap())
+        .unwrap();
+
+    let (header, claims) = message.split_once('.').unwrap();
+    assert_eq!(
+        engine.decode(header).unwrap(),
+        br#"{"alg":"HS256","typ":"JWT"}"#
+    );
+
+    serde_json::from_slice(&engine.decode(claims).unwrap()).unwrap()
+}
+
+#[test]
+#[cfg(feature = "jwt")]
+fn test_jwt() {
+    let request = request!(
+        r#"GET /reminders
+           Authorization: Bearer {{$jwt HS256 key=tests/keys/hmac.key {"sub":"alice","exp":1893456000}}}"#
+    );
+    let claims = verify(request.headers().get("Authorization").unwrap());
+    assert_eq!(
+        claims,
+        serde_json::json!({ "sub": "alice", "exp": 1893456000 })
+    );
+}
+
+#[test]
+fn test_jwt_relative_times() {
+    let request = request!(
+        r#"GET /reminders
+           Authorization: Bearer {{$jwt HS256 key=tests/keys/hmac.key {"sub":"alice","exp":"+1h","nbf":"-5m"}}}"#
+    );
+    let claims = verify(request.headers().get("Authorization").unwrap());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    assert_eq!(claims["sub"], "alice");
+    assert!((claims["exp"].as_i64().unwrap() - (now + 3600)).abs() <= 5);
+    assert!((claims["nbf"].as_i64().unwrap() - (now - 300)).abs() <= 5);
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "openapi")]
+
+use http::Method;
+use http_macros::{request, request_builder};
+
+#[test]
+fn test_openapi() {
+    let request = request!(
+        r#"# @openapi tests/openapi.yaml
+           POST /reminders
+           Host: example.com
+           Content-Type: application/json
+           X-Request-Id: abc-123
+
+           { "note": "Buy milk" }"#
+    );
+    assert_eq!(request.method(), Method::POST);
+
+    let request = request!(
+        "# @openapi tests/openapi.yaml
+         GET https://example.com/api/reminders/42"
+    );
+    assert_eq!(request.uri().path(), "/api/reminders/42");
+
+    let builder = request_builder!(
+        "# @openapi tests/openapi.yaml
+         GET /reminders?done=true"
+    );
+    assert_eq!(builder.uri_ref().unwrap().query(), Some("done=true"));
+}
+
+#[test]
+fn test_openapi_mismatch_allowed() {
+    let request = request!(
+        "# @openapi tests/openapi.yaml
+         # @allow openapi-mismatch
+         PUT /reminders"
+    );
+    assert_eq!(request.method(), Method::PUT);
+}
+
+mod reminders {
+    http_macros::openapi_requests!("tests/openapi.yaml");
+}
+
+mod raw_path {
+    http_macros::openapi_requests!(r"tests/openapi.yaml");
+}
+
+#[test]
+fn test_openapi_requests() {
+    let request = reminders::list_reminders();
+    assert_eq!(request.method(), Method::GET);
+    assert_eq!(request.uri(), "/reminders?done=false");
+
+    let request = reminders::create_reminder();
+    assert_eq!(request.method(), Method::POST);
+    assert_eq!(request.headers()["X-Request-Id"], "abc-123");
+    assert_eq!(request.headers()["Content-Type"], "application/json");
+    assert_eq!(request.body(), r#"{"note":"Buy milk"}"#);
+
+    let request = reminders::get_reminder();
+    assert_eq!(request.uri(), "/reminders/42");
+
+    let request = reminders::delete_reminder();
+    assert_eq!(request.method(), Method::DELETE);
+}
+
+#[test]
+fn test_openapi_requests_raw_path() {
+    let request = raw_path::get_reminder();
+    assert_eq!(request.uri(), "/reminders/42");
+}
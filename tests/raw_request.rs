@@ -0,0 +1,81 @@
+use http_macros::raw_request;
+
+#[test]
+fn test_request_line() {
+    let raw = raw_request!("GET /hello");
+    assert_eq!(raw, b"GET /hello HTTP/1.1\r\n\r\n");
+}
+
+#[test]
+fn test_version() {
+    let raw = raw_request!("GET /hello HTTP/1.0");
+    assert_eq!(raw, b"GET /hello HTTP/1.0\r\n\r\n");
+}
+
+#[test]
+fn test_headers_and_body() {
+    let raw = raw_request!(
+        r#"POST /reminder
+           Host: example.com
+           Content-Length: 22
+
+           { "note": "Buy milk" }"#
+    );
+    assert_eq!(
+        raw,
+        b"POST /reminder HTTP/1.1\r\nHost: example.com\r\nContent-Length: 22\r\n\r\n{ \"note\": \"Buy milk\" }"
+    );
+}
+
+#[test]
+fn test_chunked() {
+    let raw = raw_request!(
+        "# @chunk-size 4
+         POST /upload
+         Transfer-Encoding: chunked
+
+         Hello World
+         --- trailers ---
+         Expires: never"
+    );
+    assert_eq!(
+        raw,
+        b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nHell\r\n4\r\no Wo\r\n3\r\nrld\r\n0\r\nExpires: never\r\n\r\n"
+    );
+}
+
+#[test]
+fn test_chunked_utf16() {
+    let raw = raw_request!(
+        "POST /upload
+         Content-Type: text/plain; charset=UTF-16
+         Transfer-Encoding: chunked
+
+         --- chunk ---
+         Hi
+         --- chunk ---
+         Yo"
+    );
+    assert_eq!(
+        raw,
+        b"POST /upload HTTP/1.1\r\nContent-Type: text/plain; charset=UTF-16\r\nTransfer-Encoding: chunked\r\n\r\n6\r\n\xfe\xff\0H\0i\r\n4\r\n\0Y\0o\r\n0\r\n\r\n"
+    );
+}
+
+#[test]
+fn test_basic_authorization() {
+    let raw = raw_request!(
+        "GET /reminders
+         Authorization: Basic alice:secret"
+    );
+    assert_eq!(
+        raw,
+        b"GET /reminders HTTP/1.1\r\nAuthorization: Basic YWxpY2U6c2VjcmV0\r\n\r\n"
+    );
+}
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/raw_request/*.rs");
+}
@@ -1,6 +1,28 @@
+use std::{
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
 use http::Method;
+use http_body::{Body, Frame};
 use http_macros::request;
 
+/// Collects all the frames of a body that is always ready.
+fn frames<B: Body>(body: B) -> Vec<Frame<B::Data>>
+where
+    B::Error: std::fmt::Debug,
+{
+    let mut body = pin!(body);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut frames = Vec::new();
+
+    while let Poll::Ready(Some(frame)) = body.as_mut().poll_frame(&mut cx) {
+        frames.push(frame.unwrap());
+    }
+
+    frames
+}
+
 #[test]
 fn test_method() {
     let request = request!("GET /hello");
@@ -31,6 +53,26 @@ fn test_headers() {
     assert_eq!(request.headers().get("User-Agent").unwrap(), "rust-test");
 }
 
+#[test]
+fn test_repeated_headers() {
+    let request = request!(
+        "GET /hello
+         Accept: text/plain
+         Accept: text/html"
+    );
+    let accept: Vec<_> = request.headers().get_all("Accept").iter().collect();
+
+    assert_eq!(accept, ["text/plain", "text/html"]);
+
+    let request = request!(
+        "# @allow duplicate-header
+         GET /hello
+         Host: a.example.com
+         Host: b.example.com"
+    );
+    assert_eq!(request.headers().get_all("Host").iter().count(), 2);
+}
+
 #[test]
 fn test_body() {
     let request = request!(
@@ -42,6 +84,325 @@ fn test_body() {
     assert_eq!(*request.body(), "{ \"note\": \"Buy milk\" }\n");
 }
 
+#[test]
+fn test_basic_authorization() {
+    let request = request!(
+        "GET /reminders
+         Authorization: Basic alice secret"
+    );
+    assert_eq!(
+        request.headers().get("Authorization").unwrap(),
+        "Basic YWxpY2U6c2VjcmV0"
+    );
+
+    let request = request!(
+        "GET /reminders
+         Authorization: Basic YWxpY2U6c2VjcmV0"
+    );
+    assert_eq!(
+        request.headers().get("Authorization").unwrap(),
+        "Basic YWxpY2U6c2VjcmV0"
+    );
+}
+
+#[test]
+fn test_content_length() {
+    let request = request!(
+        r#"# @content-length
+           POST /reminder
+
+           { "note": "Buy milk" }"#
+    );
+    assert_eq!(request.headers().get("Content-Length").unwrap(), "22");
+
+    let request = request!(
+        r#"POST /reminder
+           Content-Length: 22
+
+           { "note": "Buy milk" }"#
+    );
+    assert_eq!(request.headers().get("Content-Length").unwrap(), "22");
+
+    let request = request!(
+        r#"# @allow content-length-mismatch
+           POST /reminder
+           Content-Length: 100
+
+           { "note": "Buy milk" }"#
+    );
+    assert_eq!(request.headers().get("Content-Length").unwrap(), "100");
+}
+
+#[test]
+fn test_chunked() {
+    let request = request!(
+        "POST /upload
+         Transfer-Encoding: chunked
+
+         --- chunk ---
+         Hello
+         --- chunk ---
+         World
+         --- trailers ---
+         Expires: never
+    "
+    );
+    let frames = frames(request.into_body());
+
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].data_ref().unwrap(), b"Hello");
+    assert_eq!(frames[1].data_ref().unwrap(), b"World");
+    assert_eq!(
+        frames[2].trailers_ref().unwrap().get("Expires").unwrap(),
+        "never"
+    );
+}
+
+#[test]
+fn test_chunked_auto() {
+    let request = request!(
+        "# @chunk-size 4
+         POST /upload
+         Transfer-Encoding: chunked
+
+         Hello World"
+    );
+    let frames = frames(request.into_body());
+    let chunks: Vec<_> = frames.iter().map(|f| *f.data_ref().unwrap()).collect();
+
+    assert_eq!(chunks, [&b"Hell"[..], b"o Wo", b"rld"]);
+}
+
+#[test]
+fn test_trailers() {
+    let request = request!(
+        "POST /greeter.Greeter/SayHello
+         Content-Type: application/grpc
+
+         Hello
+         --- trailers ---
+         Grpc-Status: 0
+         Grpc-Message: OK"
+    );
+    let frames = frames(request.into_body());
+
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].data_ref().unwrap(), b"Hello");
+
+    let trailers = frames[1].trailers_ref().unwrap();
+    assert_eq!(trailers.get("Grpc-Status").unwrap(), "0");
+    assert_eq!(trailers.get("Grpc-Message").unwrap(), "OK");
+}
+
+#[test]
+fn test_charset() {
+    let request = request!(
+        "POST /legacy
+         Content-Type: text/plain; charset=ISO-8859-1
+
+         Grüße"
+    );
+    assert_eq!(request.body(), b"Gr\xfc\xdfe");
+
+    let request = request!(
+        "# @content-length
+         POST /legacy
+         Content-Type: text/plain; charset=UTF-16LE
+
+         Hi"
+    );
+    assert_eq!(request.body(), b"H\0i\0");
+    assert_eq!(request.headers().get("Content-Length").unwrap(), "4");
+
+    let request = request!(
+        "POST /legacy
+         Content-Type: text/plain; charset=ucs-2
+
+         Hi"
+    );
+    assert_eq!(request.body(), b"H\0i\0");
+}
+
+#[test]
+fn test_json() {
+    let request = request!(
+        r#"# @json minify
+           POST /reminder
+           Content-Type: application/json
+
+           {
+               "note": "Buy milk",
+               "tags": ["shopping", "dairy"]
+           }"#
+    );
+    assert_eq!(
+        *request.body(),
+        r#"{"note":"Buy milk","tags":["shopping","dairy"]}"#
+    );
+
+    let request = request!(
+        r#"# @allow invalid-json
+           POST /reminder
+           Content-Type: application/json
+
+           { "note":"#
+    );
+    assert_eq!(*request.body(), r#"{ "note":"#);
+
+    let request = request!(
+        r#"# @json minify
+           POST /reminder
+           Content-Type: application/json
+
+           { "id": "{{$guid}}", "priority": {{$randomInt 1 10}} }"#
+    );
+    let body: serde_json::Value = serde_json::from_str(request.body()).unwrap();
+    assert_eq!(body["id"].as_str().unwrap().len(), 36);
+    assert!((1..10).contains(&body["priority"].as_i64().unwrap()));
+}
+
+#[test]
+fn test_form() {
+    let request = request!(
+        "POST /users
+         Content-Type: application/x-www-form-urlencoded
+
+         name = John Doe
+         tags = a,b"
+    );
+    assert_eq!(*request.body(), "name=John%20Doe&tags=a%2Cb");
+
+    let request = request!(
+        "POST /users
+         Content-Type: application/x-www-form-urlencoded
+
+         name=John Doe
+         &city=Zürich"
+    );
+    assert_eq!(*request.body(), "name=John%20Doe&city=Z%C3%BCrich");
+
+    let request = request!(
+        "POST /companies
+         Content-Type: application/x-www-form-urlencoded
+
+         name = Smith & Sons
+         lang = C++"
+    );
+    assert_eq!(*request.body(), "name=Smith%20%26%20Sons&lang=C%2B%2B");
+}
+
+#[test]
+fn test_multipart() {
+    let request = request!(
+        r#"POST /upload
+           Content-Type: multipart/form-data
+
+           --- part name="title" ---
+           Shopping
+
+           --- part name="note"; filename="note.txt" ---
+           Content-Type: text/plain
+
+           < tests/files/note.txt"#
+    );
+    assert_eq!(
+        request.headers()["Content-Type"],
+        "multipart/form-data; boundary=http-macros-boundary"
+    );
+    assert_eq!(
+        *request.body(),
+        b"--http-macros-boundary\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nShopping\r\n--http-macros-boundary\r\nContent-Disposition: form-data; name=\"note\"; filename=\"note.txt\"\r\nContent-Type: text/plain\r\n\r\nBuy milk\n\r\n--http-macros-boundary--\r\n"
+    );
+}
+
+#[test]
+fn test_include() {
+    let request = request!(
+        "# @json minify
+         POST /reminders
+         Content-Type: application/json
+
+         < tests/files/reminder.json"
+    );
+    assert_eq!(*request.body(), r#"{"note":"Buy milk"}"#);
+
+    let request = request!(
+        "POST /notes
+         Content-Type: text/plain
+
+         <@ ./files/note.txt"
+    );
+    assert_eq!(*request.body(), "Buy milk\n");
+
+    let request = request!(
+        "POST /pixels
+         Content-Type: image/png
+
+         < ./files/pixel.bin"
+    );
+    assert_eq!(*request.body(), b"\x89PNG\r\n\x1a\n\xff");
+
+    let request = request!(
+        "POST /notes
+         Content-Type: application/xml
+
+         <note>Buy milk</note>"
+    );
+    assert_eq!(*request.body(), "<note>Buy milk</note>");
+}
+
+#[test]
+fn test_body_encoding() {
+    let request = request!(
+        "# @body-encoding base64
+         POST /pixels
+         Content-Type: image/png
+
+         iVBORw0K
+         Ggo="
+    );
+    assert_eq!(*request.body(), b"\x89PNG\r\n\x1a\n");
+
+    let request = request!(
+        "# @body-encoding hex
+         POST /messages
+         Content-Type: application/x-protobuf
+
+         08 96 01"
+    );
+    assert_eq!(*request.body(), [0x08, 0x96, 0x01]);
+
+    let request = request!(
+        "POST /pixels
+         Content-Type: image/png
+
+         # @body-encoding base64
+         iVBORw0K
+         Ggo="
+    );
+    assert_eq!(*request.body(), b"\x89PNG\r\n\x1a\n");
+}
+
+#[test]
+fn test_graphql() {
+    let request = request!(
+        r#"POST /graphql
+           Content-Type: application/json
+           X-REQUEST-TYPE: GraphQL
+
+           query ($id: ID!) {
+             reminder(id: $id) { note }
+           }
+
+           { "id": 42 }"#
+    );
+    assert!(request.headers().get("X-REQUEST-TYPE").is_none());
+    assert_eq!(
+        *request.body(),
+        r#"{"query":"query ($id: ID!) {\nreminder(id: $id) { note }\n}","variables":{"id":42}}"#
+    );
+}
+
 #[test]
 fn ui() {
     let t = trybuild::TestCases::new();
@@ -37,6 +37,20 @@ fn test_headers() {
     );
 }
 
+#[test]
+fn test_graphql_marker() {
+    let builder = request_builder!(
+        "POST /graphql
+         Content-Type: application/json
+         X-REQUEST-TYPE: GraphQL"
+    );
+    assert!(builder
+        .headers_ref()
+        .unwrap()
+        .get("X-REQUEST-TYPE")
+        .is_none());
+}
+
 #[test]
 fn ui() {
     let t = trybuild::TestCases::new();
@@ -0,0 +1,21 @@
+#![cfg(feature = "json-schema")]
+
+use http_macros::request;
+
+#[test]
+fn test_schema() {
+    let request = request!(
+        r#"# @schema tests/schemas/reminder.json
+           POST /reminder
+           Content-Type: application/json
+
+           { "note": "Buy milk", "done": false }"#
+    );
+    assert_eq!(*request.body(), r#"{ "note": "Buy milk", "done": false }"#);
+}
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/schema/*.rs");
+}
@@ -0,0 +1,7 @@
+#![cfg(not(feature = "json-schema"))]
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/schema-disabled/*.rs");
+}
@@ -0,0 +1,30 @@
+#![cfg(all(feature = "msgpack", feature = "cbor"))]
+
+use std::collections::BTreeMap;
+
+use http_macros::request;
+
+#[test]
+fn test_msgpack() {
+    let request = request!(
+        r#"POST /reminder
+           Content-Type: application/msgpack
+
+           { "note": "Buy milk" }"#
+    );
+    let body: BTreeMap<String, String> = rmp_serde::from_slice(request.body()).unwrap();
+    assert_eq!(body["note"], "Buy milk");
+}
+
+#[test]
+fn test_cbor() {
+    let request = request!(
+        r#"POST /reminder
+           Content-Type: application/cbor
+           Content-Encoding: identity
+
+           { "note": "Buy milk" }"#
+    );
+    let body: BTreeMap<String, String> = ciborium::from_reader(&request.body()[..]).unwrap();
+    assert_eq!(body["note"], "Buy milk");
+}
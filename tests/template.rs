@@ -0,0 +1,64 @@
+use http_macros::{raw_request, request, request_builder, request_template};
+
+request_template!(
+    API,
+    "# @content-length
+     Host: api.example.com
+     Authorization: Bearer token
+     Accept: application/json
+
+     {}"
+);
+
+#[test]
+fn test_extends() {
+    let request = request!(extends API, "GET /users");
+    assert_eq!(request.uri(), "/users");
+    assert_eq!(request.headers()["Host"], "api.example.com");
+    assert_eq!(request.headers()["Authorization"], "Bearer token");
+    assert_eq!(request.headers()["Content-Length"], "2");
+    assert_eq!(request.body(), "{}");
+}
+
+#[test]
+fn test_extends_overrides() {
+    let request = request!(
+        extends API,
+        r#"POST /users
+           Accept: text/plain
+           +Accept: text/html
+           -Authorization
+
+           { "name": "John Doe" }"#
+    );
+    let accept: Vec<_> = request.headers().get_all("Accept").iter().collect();
+
+    assert_eq!(accept, ["text/plain", "text/html"]);
+    assert!(request.headers().get("Authorization").is_none());
+    assert_eq!(request.headers()["Content-Length"], "22");
+    assert_eq!(request.body(), r#"{ "name": "John Doe" }"#);
+}
+
+#[test]
+fn test_extends_builder() {
+    request_template!(LOCAL, "Host: localhost");
+
+    let builder = request_builder!(extends LOCAL, "DELETE /users/1");
+    assert_eq!(builder.headers_ref().unwrap()["Host"], "localhost");
+
+    let raw = raw_request!(extends LOCAL, "DELETE /users/1");
+    assert_eq!(raw, b"DELETE /users/1 HTTP/1.1\r\nHost: localhost\r\n\r\n");
+}
+
+#[test]
+fn test_template_with_raw_string_end() {
+    request_template!(
+        TAGGED,
+        r##"Content-Type: application/json
+
+            {"tag": "#1"}"##
+    );
+
+    let request = request!(extends TAGGED, "POST /tags");
+    assert_eq!(request.body(), r##"{"tag": "#1"}"##);
+}
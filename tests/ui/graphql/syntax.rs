@@ -0,0 +1,14 @@
+use http_macros::request;
+
+fn main() {
+    // Missing field in the selection
+    let _req = request!(
+        "POST /graphql
+         Content-Type: application/json
+         X-REQUEST-TYPE: GraphQL
+
+         query {
+           reminder(id: 1) { }
+         }"
+    );
+}
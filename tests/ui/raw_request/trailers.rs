@@ -0,0 +1,11 @@
+use http_macros::raw_request;
+
+fn main() {
+    let _raw = raw_request!(
+        "POST /hello
+
+         Hello
+         --- trailers ---
+         Grpc-Status: 0"
+    );
+}
@@ -0,0 +1,5 @@
+use http_macros::raw_request;
+
+fn main() {
+    let _raw = raw_request!("GET /hello HTTP/2.0");
+}
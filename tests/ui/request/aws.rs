@@ -0,0 +1,21 @@
+use http_macros::request;
+
+fn main() {
+    // Missing secret key
+    let _req = request!(
+        "GET https://iam.amazonaws.com/
+         Authorization: AWS AKIDEXAMPLE"
+    );
+
+    // Unknown option
+    let _req = request!(
+        "GET https://iam.amazonaws.com/
+         Authorization: AWS AKIDEXAMPLE secret zone:eu"
+    );
+
+    // Missing host
+    let _req = request!(
+        "GET /health
+         Authorization: AWS AKIDEXAMPLE secret service:execute-api"
+    );
+}
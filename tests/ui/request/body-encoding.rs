@@ -0,0 +1,35 @@
+use http_macros::request;
+
+fn main() {
+    // Unknown encoding
+    let _req = request!(
+        "# @body-encoding base32
+         POST /messages
+
+         ABC"
+    );
+
+    // Not a hex digit
+    let _req = request!(
+        "# @body-encoding hex
+         POST /messages
+
+         08 96 0g"
+    );
+
+    // Odd number of digits
+    let _req = request!(
+        "# @body-encoding hex
+         POST /messages
+
+         08 96 0"
+    );
+
+    // Invalid base64
+    let _req = request!(
+        "# @body-encoding base64
+         POST /messages
+
+         SGVs!G8="
+    );
+}
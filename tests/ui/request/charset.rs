@@ -0,0 +1,28 @@
+use http_macros::request;
+
+fn main() {
+    // Unknown charset
+    let _req = request!(
+        "POST /hello
+         Content-Type: text/plain; charset=klingon
+
+         Hello"
+    );
+
+    // Unrepresentable character
+    let _req = request!(
+        "POST /hello
+         Content-Type: text/plain; charset=ISO-8859-1
+
+         Hello
+         Wörld ✓"
+    );
+
+    // windows-1252 has `€`, but ISO-8859-1 does not
+    let _req = request!(
+        "POST /hello
+         Content-Type: text/plain; charset=latin1
+
+         5 €"
+    );
+}
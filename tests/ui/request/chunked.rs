@@ -0,0 +1,31 @@
+use http_macros::request;
+
+fn main() {
+    // Empty chunk
+    let _req = request!(
+        "POST /upload
+         Transfer-Encoding: chunked
+
+         --- chunk ---
+         --- chunk ---
+         Hello"
+    );
+
+    // Content-Length with chunked
+    let _req = request!(
+        "POST /upload
+         Transfer-Encoding: chunked
+         Content-Length: 5
+
+         Hello"
+    );
+
+    // Invalid chunk size
+    let _req = request!(
+        "# @chunk-size 0
+         POST /upload
+         Transfer-Encoding: chunked
+
+         Hello"
+    );
+}
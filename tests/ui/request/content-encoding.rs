@@ -0,0 +1,21 @@
+use http_macros::request;
+
+fn main() {
+    // Unknown coding
+    let _req = request!(
+        "POST /hello
+         Content-Encoding: lzma
+
+         Hello"
+    );
+
+    // Explicit chunks
+    let _req = request!(
+        "POST /hello
+         Content-Encoding: lzma
+         Transfer-Encoding: chunked
+
+         --- chunk ---
+         Hello"
+    );
+}
@@ -0,0 +1,23 @@
+use http_macros::request;
+
+fn main() {
+    // Wrong length
+    let _req = request!(
+        "POST /hello
+         Content-Length: 3
+
+         Hello"
+    );
+
+    // Unknown directive
+    let _req = request!(
+        "# @content-size
+         POST /hello"
+    );
+
+    // Unknown lint
+    let _req = request!(
+        "# @allow length-mismatch
+         POST /hello"
+    );
+}
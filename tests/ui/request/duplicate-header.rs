@@ -0,0 +1,17 @@
+use http_macros::request;
+
+fn main() {
+    let _req = request!(
+        "GET /hello
+         Host: a.example.com
+         Host: b.example.com"
+    );
+
+    let _req = request!(
+        "POST /hello
+         content-length: 5
+         Content-Length: 5
+
+         Hello"
+    );
+}
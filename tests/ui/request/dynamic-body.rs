@@ -0,0 +1,19 @@
+use http_macros::request;
+
+fn main() {
+    // The length is only known when the request is made
+    let _req = request!(
+        "# @content-length
+         POST /notes
+
+         id={{$guid}}"
+    );
+
+    // Form encoding would change the variable
+    let _req = request!(
+        "POST /notes
+         Content-Type: application/x-www-form-urlencoded
+
+         id = {{$guid}}"
+    );
+}
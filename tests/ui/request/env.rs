@@ -0,0 +1,16 @@
+use http_macros::request;
+
+fn main() {
+    // Missing environment variable
+    let _req = request!(
+        "GET /notes
+         X-Api-Key: {{$processEnv HTTP_MACROS_UI_MISSING}}"
+    );
+
+    // Unknown resolution
+    let _req = request!(
+        "# @resolve-env later
+         GET /notes
+         X-Api-Key: {{$processEnv HTTP_MACROS_UI_MISSING}}"
+    );
+}
@@ -0,0 +1,15 @@
+use http_macros::request;
+
+fn main() {
+    // Undefined variable
+    let _req = request!(
+        "@version = v1
+         GET {{baseUrl}}/{{version}}/notes"
+    );
+
+    // Unknown environment
+    let _req = request!(
+        "# @environment production
+         GET {{baseUrl}}/notes"
+    );
+}
@@ -0,0 +1,16 @@
+use http_macros::request;
+
+fn main() {
+    // Cycle
+    let _req = request!(
+        "@host = {{url}}
+         @url = http://{{host}}
+         GET {{url}}/notes"
+    );
+
+    // Missing `=`
+    let _req = request!(
+        "@host localhost
+         GET /notes"
+    );
+}
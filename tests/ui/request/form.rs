@@ -0,0 +1,20 @@
+use http_macros::request;
+
+fn main() {
+    // Missing `=`
+    let _req = request!(
+        "POST /users
+         Content-Type: application/x-www-form-urlencoded
+
+         name = John Doe
+         tags"
+    );
+
+    // Missing name
+    let _req = request!(
+        "POST /users
+         Content-Type: application/x-www-form-urlencoded
+
+         = John Doe"
+    );
+}
@@ -0,0 +1,15 @@
+use http_macros::request;
+
+fn main() {
+    // Invalid JSON variables
+    let _req = request!(
+        r#"POST /graphql
+           X-REQUEST-TYPE: GraphQL
+
+           query ($id: ID!) {
+             reminder(id: $id) { note }
+           }
+
+           { "id": }"#
+    );
+}
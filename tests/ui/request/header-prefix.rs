@@ -0,0 +1,16 @@
+use http_macros::request;
+
+fn main() {
+    // Removing a header outside of a template
+    let _req = request!(
+        "GET /hello
+         Host: example.com
+         -Host"
+    );
+
+    // Adding a value outside of a template
+    let _req = request!(
+        "GET /hello
+         +Accept: text/html"
+    );
+}
@@ -0,0 +1,23 @@
+use http_macros::request;
+
+fn main() {
+    // Missing comma
+    let _req = request!(
+        r#"POST /reminder
+           Content-Type: application/json
+
+           {
+               "note": "Buy milk"
+               "done": false
+           }"#
+    );
+
+    // Unknown format
+    let _req = request!(
+        r#"# @json pretty
+           POST /reminder
+           Content-Type: application/json
+
+           {}"#
+    );
+}
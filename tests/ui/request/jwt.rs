@@ -0,0 +1,21 @@
+use http_macros::request;
+
+fn main() {
+    // Unsupported algorithm
+    let _req = request!(
+        r#"GET /reminders
+           Authorization: Bearer {{$jwt RS256 key=tests/keys/rsa.pem {"sub":"alice"}}}"#
+    );
+
+    // Missing key
+    let _req = request!(
+        r#"GET /reminders
+           Authorization: Bearer {{$jwt HS256 {"sub":"alice"}}}"#
+    );
+
+    // Unknown dynamic variable
+    let _req = request!(
+        "GET /reminders
+         X-Request-Id: {{$requestId}}"
+    );
+}
@@ -0,0 +1,29 @@
+use http_macros::request;
+
+fn main() {
+    // Missing part marker
+    let _req = request!(
+        r#"POST /upload
+           Content-Type: multipart/form-data
+
+           Shopping"#
+    );
+
+    // Missing name
+    let _req = request!(
+        r#"POST /upload
+           Content-Type: multipart/form-data
+
+           --- part filename="note.txt" ---
+           Buy milk"#
+    );
+
+    // Boundary in a part
+    let _req = request!(
+        r#"POST /upload
+           Content-Type: multipart/form-data; boundary=milk
+
+           --- part name="note" ---
+           Buy milk"#
+    );
+}
@@ -0,0 +1,11 @@
+use http_macros::request;
+
+fn main() {
+    // Invalid JSON cannot be converted
+    let _req = request!(
+        r#"POST /reminder
+           Content-Type: application/msgpack
+
+           { "note": }"#
+    );
+}
@@ -0,0 +1,16 @@
+use http_macros::{request, request_template};
+
+// Request line in the template
+request_template!(
+    BASE,
+    "GET /users
+     Host: example.com"
+);
+
+fn main() {
+    // Missing template name
+    let _req = request!(extends "GET /users");
+
+    // Missing `,` after the template name
+    let _req = request!(extends BASE; "GET /users");
+}
@@ -0,0 +1,21 @@
+use http_macros::request;
+
+fn main() {
+    // Invalid trailer name
+    let _req = request!(
+        "POST /upload
+
+         Hello
+         --- trailers ---
+         Grpc Status: 0"
+    );
+
+    // Invalid trailer value
+    let _req = request!(
+        "POST /upload
+
+         Hello
+         --- trailers ---
+         Grpc-Message: Grüße"
+    );
+}
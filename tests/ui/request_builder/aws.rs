@@ -0,0 +1,15 @@
+use http_macros::request_builder;
+
+fn main() {
+    let _builder = request_builder!(
+        "GET /?Action=ListUsers&Version=2010-05-08
+         Host: iam.amazonaws.com
+         Authorization: AWS AKIDEXAMPLE wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY region:us-east-1 service:iam"
+    );
+
+    let _builder = request_builder!(
+        "# @aws-date 20150830T123600Z
+         GET /
+         Host: iam.amazonaws.com"
+    );
+}
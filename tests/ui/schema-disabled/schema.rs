@@ -0,0 +1,11 @@
+use http_macros::request;
+
+fn main() {
+    let _req = request!(
+        r#"# @schema tests/schemas/reminder.json
+           POST /reminder
+           Content-Type: application/json
+
+           { "note": "Buy milk" }"#
+    );
+}
@@ -0,0 +1,12 @@
+use http_macros::request;
+
+// trybuild builds this file from `target/tests/trybuild/http-macros`, so the schema path goes back to the repo
+fn main() {
+    let _req = request!(
+        r#"# @schema ../../../../tests/schemas/reminder.json
+           POST /reminder
+           Content-Type: application/json
+
+           { "note": 42, "due": "today" }"#
+    );
+}